@@ -0,0 +1,290 @@
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+use crate::key_store;
+use crate::signed_request;
+use crate::state::AppState;
+
+/// What an API key or request signer may do. Each role includes the ones before it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    /// Queries and pure checks.
+    ReadOnly,
+    /// Building instructions, generating keypairs and requesting airdrops.
+    Builder,
+    /// Anything that takes a secret key.
+    Signer,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::ReadOnly => "read-only",
+            Role::Builder => "builder",
+            Role::Signer => "signer",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read-only" => Ok(Role::ReadOnly),
+            "builder" => Ok(Role::Builder),
+            "signer" => Ok(Role::Signer),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("expected read-only, builder, signer or admin, got '{}'", other)),
+        }
+    }
+}
+
+/// Role needed for an API route (path without the API prefix), or `None`
+/// for public routes. Routes missing from the table need `admin`, so a new
+/// route is closed until it is listed here.
+pub fn required_role(route: &str) -> Option<Role> {
+    match route {
+        "/hello" | "/auth/nonce" | "/auth/login" | "/auth/logout" | "/auth/session" => None,
+        "/balance" | "/message/verify" => Some(Role::ReadOnly),
+        "/airdrop" | "/keypair" | "/token/create" | "/token/mint" | "/send/sol" | "/send/token" => {
+            Some(Role::Builder)
+        }
+        "/message/sign" => Some(Role::Signer),
+        _ => Some(Role::Admin),
+    }
+}
+
+/// The API key or request signer a request was made with, left in the
+/// request extensions by [`authenticate`]. Wallet sessions do not get one:
+/// anyone can make a new wallet, so they are rate limited per client IP like
+/// anonymous requests.
+#[derive(Clone, Debug)]
+pub struct AuthenticatedKey {
+    pub id: String,
+}
+
+/// The API key in `Authorization: Bearer`. Other bearer tokens, such as
+/// SIWS sessions, are left to their own extractors.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| key_store::is_api_key(token))
+}
+
+/// Who a request authenticated as.
+struct Caller {
+    /// Key id, or the signer's or wallet's public key.
+    id: String,
+    role: Role,
+    /// Signed in with a SIWS session rather than an issued credential.
+    wallet: bool,
+}
+
+/// Route middleware authenticating the request and checking the caller
+/// against the route's [`required_role`]. Callers either send
+/// `Authorization: Bearer <key>`, checked against the key store, sign the
+/// request with a keypair from `auth.signers` (see [`signed_request`]), or
+/// present a Sign-In With Solana session (`Bearer ses_...` or the session
+/// cookie), which carries `siws.session_role`. Credentials sent to a public
+/// route are still checked, so the caller is identified for rate limiting;
+/// a stale session there is ignored, so the wallet can sign in again.
+///
+/// Meant for `route_layer` on the API router, where the URI no longer
+/// carries the API prefix.
+pub async fn authenticate(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(keys) = &state.key_store else {
+        return next.run(request).await;
+    };
+    let required = required_role(request.uri().path());
+
+    let (mut request, caller) = if signed_request::is_signed(request.headers()) {
+        match verify_signed(&state, request).await {
+            Ok((request, caller)) => (request, Some(caller)),
+            Err(response) => return response,
+        }
+    } else {
+        let caller = match bearer_token(request.headers()) {
+            Some(token) => match keys.lookup(token) {
+                Some(key) => Some(Caller { id: key.id, role: key.role, wallet: false }),
+                None => return unauthorized("invalid or revoked API key"),
+            },
+            None => match state.siws.authenticate(request.headers()) {
+                Some(Ok((pubkey, role))) => Some(Caller { id: pubkey.to_string(), role, wallet: true }),
+                Some(Err(reason)) if required.is_some() => return unauthorized(reason),
+                Some(Err(_)) | None => None,
+            },
+        };
+        (request, caller)
+    };
+
+    match (required, &caller) {
+        (Some(_), None) => return unauthorized("this route needs an API key, a signed request or a wallet session"),
+        (Some(required), Some(caller)) if caller.role < required => {
+            return ApiError::InsufficientRole { required, actual: caller.role }.into_response();
+        }
+        _ => {}
+    }
+
+    if let Some(caller) = caller {
+        tracing::Span::current().record("api_key", caller.id.as_str());
+        tracing::debug!(role = %caller.role, "authenticated");
+        if !caller.wallet {
+            request.extensions_mut().insert(AuthenticatedKey { id: caller.id });
+        }
+    }
+    next.run(request).await
+}
+
+/// Buffer the body, which the signature covers, and verify the request.
+/// Hands back a request with the same body for the handler.
+async fn verify_signed(state: &AppState, request: Request) -> Result<(Request, Caller), Response> {
+    let Some(signers) = &state.request_signers else {
+        return Err(unauthorized("signed requests are not enabled"));
+    };
+    let (parts, body) = request.into_parts();
+    let limit = state.config.server.body_limit;
+    let body = axum::body::to_bytes(body, limit).await.map_err(|_| {
+        ApiError::PayloadTooLarge(format!("Request body is larger than {} bytes", limit)).into_response()
+    })?;
+
+    // Clients sign the URI they sent, API prefix included
+    let uri = parts.extensions.get::<OriginalUri>().map_or(&parts.uri, |original| &original.0);
+    let path = uri.path_and_query().map_or(uri.path(), |path| path.as_str());
+    let (pubkey, role) = signers
+        .verify(&parts.method, path, &body, &parts.headers)
+        .map_err(|reason| unauthorized(&reason))?;
+
+    let caller = Caller { id: pubkey.to_string(), role, wallet: false };
+    Ok((Request::from_parts(parts, Body::from(body)), caller))
+}
+
+fn unauthorized(reason: &str) -> Response {
+    let mut response = ApiError::Unauthorized { reason: reason.to_string() }.into_response();
+    response
+        .headers_mut()
+        .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::{http::StatusCode, middleware, routing::post, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::config::Config;
+
+    const BUILDER_ROUTES: [&str; 6] =
+        ["/airdrop", "/keypair", "/token/create", "/token/mint", "/send/sol", "/send/token"];
+    const PUBLIC_ROUTES: [&str; 5] = ["/hello", "/auth/nonce", "/auth/login", "/auth/logout", "/auth/session"];
+
+    /// Keys issued for each role, one revoked, and every route answering 200
+    /// behind [`authenticate`].
+    struct TestApp {
+        router: Router,
+        keys: HashMap<Role, String>,
+        revoked: String,
+    }
+
+    fn app(name: &str) -> TestApp {
+        let dir = std::env::temp_dir().join(format!("auth-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let keys_file = dir.join("api_keys.json");
+
+        let keys = [Role::ReadOnly, Role::Builder, Role::Signer, Role::Admin]
+            .into_iter()
+            .map(|role| (role, key_store::create(&keys_file, role, None).unwrap().1))
+            .collect();
+        let (record, revoked) = key_store::create(&keys_file, Role::Admin, None).unwrap();
+        key_store::revoke(&keys_file, &record.id).unwrap();
+
+        let mut config = Config::default();
+        config.auth.enabled = true;
+        config.auth.keys_file = keys_file;
+        let state = AppState::new(config).unwrap();
+
+        let router = BUILDER_ROUTES
+            .into_iter()
+            .chain(PUBLIC_ROUTES)
+            .chain(["/balance", "/message/sign", "/internal/debug"])
+            .fold(Router::new(), |router, route| router.route(route, post(|| async { "ok" })))
+            .route_layer(middleware::from_fn_with_state(state.clone(), authenticate))
+            .with_state(state);
+        TestApp { router, keys, revoked }
+    }
+
+    impl TestApp {
+        async fn status(&self, route: &str, key: Option<&str>) -> StatusCode {
+            let mut request = Request::post(route);
+            if let Some(key) = key {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", key));
+            }
+            let request = request.body(Body::empty()).unwrap();
+            self.router.clone().oneshot(request).await.unwrap().status()
+        }
+
+        fn key(&self, role: Role) -> Option<&str> {
+            Some(&self.keys[&role])
+        }
+    }
+
+    #[tokio::test]
+    async fn read_only_key_cannot_build() {
+        let app = app("read-only");
+        assert_eq!(app.status("/balance", app.key(Role::ReadOnly)).await, StatusCode::OK);
+        for route in BUILDER_ROUTES {
+            assert_eq!(app.status(route, app.key(Role::ReadOnly)).await, StatusCode::FORBIDDEN, "{route}");
+            assert_eq!(app.status(route, app.key(Role::Builder)).await, StatusCode::OK, "{route}");
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_unknown_or_revoked_key_is_unauthorized() {
+        let app = app("unauthorized");
+        let unknown = "sk_00000000_1111111111111111111111111111111";
+        for key in [None, Some(unknown), Some(app.revoked.as_str())] {
+            assert_eq!(app.status("/balance", key).await, StatusCode::UNAUTHORIZED, "{key:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn public_routes_need_no_key() {
+        let app = app("public");
+        for route in PUBLIC_ROUTES {
+            assert_eq!(required_role(route), None);
+            assert_eq!(app.status(route, None).await, StatusCode::OK, "{route}");
+        }
+    }
+
+    #[tokio::test]
+    async fn unlisted_routes_need_admin() {
+        let app = app("admin");
+        assert_eq!(required_role("/internal/debug"), Some(Role::Admin));
+        assert_eq!(app.status("/internal/debug", app.key(Role::Signer)).await, StatusCode::FORBIDDEN);
+        assert_eq!(app.status("/internal/debug", app.key(Role::Admin)).await, StatusCode::OK);
+    }
+}
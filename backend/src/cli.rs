@@ -0,0 +1,76 @@
+use crate::auth::Role;
+use crate::config::Config;
+use crate::key_store::{self, KeyRecord};
+
+const USAGE: &str = "\
+usage:
+  backend                                         run the server
+  backend keys create --role <role> [--label <text>]
+  backend keys revoke <id>
+  backend keys list
+
+roles: read-only, builder, signer, admin";
+
+/// Run the admin subcommand in `args` (without the program name), if any,
+/// and return the process exit code. Returns `None` when the server should
+/// start instead.
+pub fn run(config: &Config, args: &[String]) -> Option<i32> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        [] => return None,
+        ["keys", "create", rest @ ..] => create(config, rest),
+        ["keys", "revoke", id] => revoke(config, id),
+        ["keys", "list"] => list(config),
+        ["help" | "--help" | "-h"] => {
+            println!("{}", USAGE);
+            return Some(0);
+        }
+        _ => Err(format!("unrecognised arguments: {}", args.join(" "))),
+    };
+    Some(match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            1
+        }
+    })
+}
+
+fn create(config: &Config, args: &[&str]) -> Result<(), String> {
+    let mut role = None;
+    let mut label = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        match *arg {
+            "--role" => role = Some(value.parse::<Role>()?),
+            "--label" => label = Some(value.to_string()),
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+    let role = role.ok_or("--role is required")?;
+
+    let (record, key) = key_store::create(&config.auth.keys_file, role, label).map_err(|e| e.to_string())?;
+    println!("created {} key {}", record.role, record.id);
+    println!("{}", key);
+    eprintln!("store it now, it cannot be shown again");
+    Ok(())
+}
+
+fn revoke(config: &Config, id: &str) -> Result<(), String> {
+    let record = key_store::revoke(&config.auth.keys_file, id).map_err(|e| e.to_string())?;
+    println!("revoked {} key {}", record.role, record.id);
+    Ok(())
+}
+
+fn list(config: &Config) -> Result<(), String> {
+    let keys = key_store::list(&config.auth.keys_file).map_err(|e| e.to_string())?;
+    for KeyRecord { id, role, label, created_at, revoked_at, .. } in keys {
+        let status = match revoked_at {
+            Some(at) => format!("revoked at {}", at),
+            None => "active".to_string(),
+        };
+        println!("{}\t{}\tcreated at {}\t{}\t{}", id, role, created_at, status, label.unwrap_or_default());
+    }
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::RpcConfig;
+use crate::error::ApiError;
+use crate::metrics::Metrics;
+use crate::rpc_pool::RpcPool;
+use crate::state::AppState;
+
+pub const CLUSTER_HEADER: &str = "x-solana-cluster";
+const CLUSTER_QUERY_PARAM: &str = "cluster";
+
+/// A configured Solana cluster and the nodes used to talk to it.
+pub struct Cluster {
+    pub name: String,
+    pub rpc: Arc<RpcPool>,
+    pub airdrop: bool,
+}
+
+/// Every configured cluster, keyed by name.
+pub struct Clusters {
+    clusters: HashMap<String, Arc<Cluster>>,
+    default: String,
+}
+
+impl Clusters {
+    pub fn new(config: &RpcConfig, metrics: Arc<Metrics>) -> Self {
+        let clusters = config
+            .clusters
+            .iter()
+            .map(|(name, cluster)| {
+                let rpc = RpcPool::new(
+                    name,
+                    &cluster.urls(),
+                    config.timeout(),
+                    config.commitment.config(),
+                    config.max_slot_lag,
+                    metrics.clone(),
+                );
+                let cluster = Cluster {
+                    name: name.clone(),
+                    rpc: Arc::new(rpc),
+                    airdrop: cluster.airdrop,
+                };
+                (name.clone(), Arc::new(cluster))
+            })
+            .collect();
+        Self {
+            clusters,
+            default: config.default_cluster.clone(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Cluster>> {
+        self.clusters.get(name).cloned()
+    }
+
+    /// Start a background health checker for every cluster's pool.
+    pub fn spawn_health_checks(&self, interval: Duration, shutdown: &CancellationToken) -> Vec<JoinHandle<()>> {
+        self.clusters
+            .values()
+            .map(|cluster| cluster.rpc.clone().spawn_health_checks(interval, shutdown.clone()))
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Cluster>> {
+        self.clusters.values()
+    }
+
+    pub fn default_name(&self) -> &str {
+        &self.default
+    }
+
+    pub fn default_cluster(&self) -> Arc<Cluster> {
+        // Config validation guarantees the default cluster exists.
+        self.clusters[&self.default].clone()
+    }
+}
+
+/// The cluster a request targets: the `X-Solana-Cluster` header if present,
+/// else the `cluster` query parameter, else the configured default.
+pub struct SelectedCluster(pub Arc<Cluster>);
+
+/// Name of the cluster the request asked for, or `None` for the default.
+fn requested_name(parts: &Parts) -> Option<String> {
+    let from_header = parts
+        .headers
+        .get(CLUSTER_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let from_query = || {
+        form_urlencoded::parse(parts.uri.query().unwrap_or_default().as_bytes())
+            .find(|(key, _)| key == CLUSTER_QUERY_PARAM)
+            .map(|(_, value)| value.into_owned())
+    };
+    from_header.or_else(from_query)
+}
+
+/// The requested cluster, or the name that matched no configured cluster.
+fn select(parts: &Parts, state: &AppState) -> Result<Arc<Cluster>, String> {
+    match requested_name(parts) {
+        None => Ok(state.clusters.default_cluster()),
+        Some(name) => state.clusters.get(&name).ok_or(name),
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for SelectedCluster {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        select(parts, state)
+            .map(SelectedCluster)
+            .map_err(|name| ApiError::UnknownCluster { name })
+    }
+}
+
+/// Like [`SelectedCluster`], for handlers that only sometimes talk to the
+/// cluster. An unknown cluster name is only an error once
+/// [`LazyCluster::get`] is called, so pure instruction builds ignore it.
+pub struct LazyCluster {
+    selected: Result<Arc<Cluster>, String>,
+    used: AtomicBool,
+}
+
+impl LazyCluster {
+    pub fn get(&self) -> Result<Arc<Cluster>, ApiError> {
+        let cluster = self
+            .selected
+            .clone()
+            .map_err(|name| ApiError::UnknownCluster { name })?;
+        self.used.store(true, Ordering::Relaxed);
+        Ok(cluster)
+    }
+
+    /// Name of the cluster, if the handler used it. Echoed in responses like
+    /// the RPC-backed handlers do.
+    pub fn used_name(&self) -> Option<String> {
+        match &self.selected {
+            Ok(cluster) if self.used.load(Ordering::Relaxed) => Some(cluster.name.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for LazyCluster {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        Ok(LazyCluster {
+            selected: select(parts, state),
+            used: AtomicBool::new(false),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::commitment_config::CommitmentConfig;
+
+    use super::*;
+
+    fn lazy(selected: Result<&str, &str>) -> LazyCluster {
+        let selected = selected
+            .map(|name| {
+                let rpc = RpcPool::new(
+                    name,
+                    &["http://127.0.0.1:8899".to_string()],
+                    Duration::from_secs(1),
+                    CommitmentConfig::confirmed(),
+                    50,
+                    Arc::new(Metrics::new()),
+                );
+                Arc::new(Cluster { name: name.to_string(), rpc: Arc::new(rpc), airdrop: true })
+            })
+            .map_err(str::to_string);
+        LazyCluster { selected, used: AtomicBool::new(false) }
+    }
+
+    #[test]
+    fn lazy_cluster_reports_name_only_once_used() {
+        let cluster = lazy(Ok("devnet"));
+        assert_eq!(cluster.used_name(), None);
+        assert_eq!(cluster.get().unwrap().name, "devnet");
+        assert_eq!(cluster.used_name().as_deref(), Some("devnet"));
+    }
+
+    #[test]
+    fn unknown_lazy_cluster_fails_only_when_used() {
+        let cluster = lazy(Err("nope"));
+        assert_eq!(cluster.used_name(), None);
+        assert!(matches!(cluster.get(), Err(ApiError::UnknownCluster { name }) if name == "nope"));
+    }
+}
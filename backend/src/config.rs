@@ -0,0 +1,821 @@
+use std::{collections::{BTreeMap, HashSet}, env, fs, net::SocketAddr, path::PathBuf, time::Duration};
+
+use axum::http::{HeaderName, Method};
+use serde::Deserialize;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::auth::Role;
+use crate::cors::OriginRule;
+use crate::rate_limit::parse_proxy;
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+
+    #[error("failed to parse config file {path}: {source}")]
+    Parse { path: PathBuf, source: toml::de::Error },
+
+    #[error("invalid value for {key}: {reason}")]
+    Invalid { key: String, reason: String },
+}
+
+/// Service configuration, read from a TOML file (`CONFIG_PATH`, default
+/// `config.toml`) and then overridden by environment variables.
+///
+/// Every section is optional; anything left out falls back to the defaults
+/// below, which match the behaviour of the service before it was
+/// configurable.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// File the config was read from; `None` when running on defaults.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    pub server: ServerConfig,
+    pub cors: CorsConfig,
+    pub rpc: RpcConfig,
+    pub endpoints: EndpointsConfig,
+    pub logging: LoggingConfig,
+    pub metrics: MetricsConfig,
+    pub shutdown: ShutdownConfig,
+    pub frontend: FrontendConfig,
+    pub rate_limit: RateLimitConfig,
+    pub auth: AuthConfig,
+    pub siws: SiwsConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Env: `BIND_ADDR`
+    pub bind: SocketAddr,
+    /// Maximum request body size in bytes. Env: `BODY_LIMIT_BYTES`
+    pub body_limit: usize,
+    /// Path the JSON API is mounted under, e.g. `/api/balance`. Health and
+    /// metrics endpoints stay at the root. Env: `API_PREFIX`
+    pub api_prefix: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: ([0, 0, 0, 0], 8001).into(),
+            body_limit: 2 * 1024 * 1024,
+            api_prefix: "/api".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Exact origins (`https://app.example.com`), subdomain patterns
+    /// (`https://*.example.com`) or `*` for any origin. Comma separated in
+    /// env: `CORS_ALLOWED_ORIGINS`
+    pub allowed_origins: Vec<String>,
+    /// Request headers the browser may send; `*` allows any.
+    pub allowed_headers: Vec<String>,
+    /// `*` allows any.
+    pub allowed_methods: Vec<String>,
+    /// Response headers scripts may read.
+    pub exposed_headers: Vec<String>,
+    /// Let the browser send cookies and `Authorization` with cross-origin
+    /// requests. Cannot be combined with a `*` origin. Env:
+    /// `CORS_ALLOW_CREDENTIALS`
+    pub allow_credentials: bool,
+    /// How long browsers may cache a preflight response. Unset leaves it to
+    /// the browser default.
+    pub max_age_secs: Option<u64>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["https://superdev.dhruvdeora.com".to_string()],
+            allowed_headers: vec![
+                "content-type".to_string(),
+                "authorization".to_string(),
+                crate::cluster::CLUSTER_HEADER.to_string(),
+                crate::telemetry::REQUEST_ID_HEADER.to_string(),
+            ],
+            allowed_methods: vec!["GET".to_string(), "POST".to_string(), "PUT".to_string(), "DELETE".to_string()],
+            exposed_headers: vec![crate::telemetry::REQUEST_ID_HEADER.to_string()],
+            allow_credentials: false,
+            max_age_secs: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    /// Named clusters a request can pick with `X-Solana-Cluster` or
+    /// `?cluster=`.
+    pub clusters: BTreeMap<String, ClusterConfig>,
+    /// Cluster used when a request does not name one. Env: `SOLANA_CLUSTER`
+    pub default_cluster: String,
+    /// Env: `SOLANA_COMMITMENT`
+    pub commitment: Commitment,
+    /// Per-request RPC timeout in seconds. Env: `SOLANA_RPC_TIMEOUT_SECS`
+    pub timeout_secs: u64,
+    /// How often every node is probed with `getHealth` / `getSlot`.
+    pub health_check_interval_secs: u64,
+    /// A node more than this many slots behind the best node in its cluster
+    /// is taken out of rotation.
+    pub max_slot_lag: u64,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        let devnet = ClusterConfig {
+            url: "https://api.devnet.solana.com".to_string(),
+            fallback_urls: Vec::new(),
+            ws_url: None,
+            airdrop: true,
+        };
+        Self {
+            clusters: BTreeMap::from([("devnet".to_string(), devnet)]),
+            default_cluster: "devnet".to_string(),
+            commitment: Commitment::Confirmed,
+            timeout_secs: 30,
+            health_check_interval_secs: 10,
+            max_slot_lag: 50,
+        }
+    }
+}
+
+impl RpcConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn health_check_interval(&self) -> Duration {
+        Duration::from_secs(self.health_check_interval_secs)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterConfig {
+    /// Primary node. For the default cluster, env: `SOLANA_RPC_URL`
+    pub url: String,
+    /// Further nodes to fail over to when the primary is down or lagging.
+    #[serde(default)]
+    pub fallback_urls: Vec<String>,
+    /// Derived from `url` when unset. For the default cluster, env:
+    /// `SOLANA_WS_URL`
+    #[serde(default)]
+    pub ws_url: Option<String>,
+    /// Whether `/airdrop` may be used against this cluster. Off by default so
+    /// a mainnet entry never gets faucet requests.
+    #[serde(default)]
+    pub airdrop: bool,
+}
+
+impl ClusterConfig {
+    /// Primary URL followed by the fallbacks.
+    pub fn urls(&self) -> Vec<String> {
+        std::iter::once(self.url.clone())
+            .chain(self.fallback_urls.iter().cloned())
+            .collect()
+    }
+
+    pub fn ws_url(&self) -> String {
+        match &self.ws_url {
+            Some(url) => url.clone(),
+            None => self
+                .url
+                .replacen("https://", "wss://", 1)
+                .replacen("http://", "ws://", 1),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn config(self) -> CommitmentConfig {
+        match self {
+            Commitment::Processed => CommitmentConfig::processed(),
+            Commitment::Confirmed => CommitmentConfig::confirmed(),
+            Commitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+impl std::str::FromStr for Commitment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "processed" => Ok(Commitment::Processed),
+            "confirmed" => Ok(Commitment::Confirmed),
+            "finalized" => Ok(Commitment::Finalized),
+            other => Err(format!("expected processed, confirmed or finalized, got '{}'", other)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// `tracing` filter directives, e.g. `info,backend::rpc_pool=debug`.
+    /// `RUST_LOG` takes precedence when set.
+    pub filter: String,
+    /// Env: `LOG_FORMAT`
+    pub format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+            format: LogFormat::Pretty,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("expected pretty or json, got '{}'", other)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Serve Prometheus metrics on `/metrics`.
+    pub enabled: bool,
+    /// Serve `/metrics` on this separate address instead of the public one.
+    /// Env: `METRICS_ADMIN_BIND`
+    pub admin_bind: Option<SocketAddr>,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            admin_bind: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownConfig {
+    /// After SIGTERM/SIGINT, keep accepting requests for this long while
+    /// `/readyz` reports not ready, so load balancers can drain us first.
+    pub readiness_delay_secs: u64,
+    /// Then give in-flight requests this long to finish before exiting.
+    /// Env: `SHUTDOWN_DRAIN_TIMEOUT_SECS`
+    pub drain_timeout_secs: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            readiness_delay_secs: 0,
+            drain_timeout_secs: 30,
+        }
+    }
+}
+
+impl ShutdownConfig {
+    pub fn readiness_delay(&self) -> Duration {
+        Duration::from_secs(self.readiness_delay_secs)
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrontendConfig {
+    /// Serve the built SPA at `/`.
+    pub enabled: bool,
+    /// Vite build output. Env: `FRONTEND_DIST_DIR`
+    pub dist_dir: PathBuf,
+    /// Development only: proxy every non-API path, including the HMR
+    /// WebSocket, to the Vite dev server at this URL instead of serving
+    /// `dist_dir`. Env: `FRONTEND_DEV_SERVER_URL`
+    pub dev_server_url: Option<String>,
+}
+
+impl Default for FrontendConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dist_dir: PathBuf::from("../dist"),
+            dev_server_url: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Env: `RATE_LIMIT_ENABLED`
+    pub enabled: bool,
+    /// Proxies (addresses or CIDR ranges) whose `X-Forwarded-For` is
+    /// believed. Comma separated in env: `RATE_LIMIT_TRUSTED_PROXIES`
+    pub trusted_proxies: Vec<String>,
+    /// Bucket for every API route not listed in `routes`.
+    pub default: BucketConfig,
+    /// Per-route buckets, keyed by path without the API prefix, e.g.
+    /// `/airdrop`. Replaces the built-in list when set.
+    pub routes: BTreeMap<String, BucketConfig>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            trusted_proxies: Vec::new(),
+            default: BucketConfig {
+                requests: 120,
+                period_secs: 60,
+                refund_failures: false,
+            },
+            // Every airdrop spends our own faucet allowance.
+            routes: BTreeMap::from([(
+                "/airdrop".to_string(),
+                BucketConfig {
+                    requests: 2,
+                    period_secs: 3600,
+                    refund_failures: true,
+                },
+            )]),
+        }
+    }
+}
+
+/// A client may burst up to `requests`, refilled evenly over `period_secs`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BucketConfig {
+    pub requests: u32,
+    pub period_secs: u64,
+    /// Give the token back when the request is refused (4xx) or no RPC node
+    /// was reachable (503), i.e. when the work it pays for never happened.
+    #[serde(default)]
+    pub refund_failures: bool,
+}
+
+/// API key and signed request authentication. Keys are managed with
+/// `backend keys ...`; request signers are listed here.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Require an API key or a signed request on every non-public route.
+    /// Env: `AUTH_ENABLED`
+    pub enabled: bool,
+    /// JSON file holding hashed keys; created by the first `keys create`.
+    /// Env: `API_KEYS_FILE`
+    pub keys_file: PathBuf,
+    /// Keypairs whose signed requests are accepted in place of an API key.
+    pub signers: Vec<RequestSignerConfig>,
+    /// How far a signed request's timestamp may be from the server clock.
+    /// Signatures are remembered this long to refuse replays.
+    pub max_clock_skew_secs: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keys_file: PathBuf::from("api_keys.json"),
+            signers: Vec::new(),
+            max_clock_skew_secs: 300,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RequestSignerConfig {
+    /// Base58 public key of the client's keypair.
+    pub pubkey: String,
+    pub role: Role,
+}
+
+/// Sign-In With Solana (`/auth/*`).
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiwsConfig {
+    /// Domain sign-in messages must name, i.e. the frontend's host.
+    /// Env: `SIWS_DOMAIN`
+    pub domain: String,
+    /// How long a nonce from `/auth/nonce` can be used to sign in.
+    pub nonce_ttl_secs: u64,
+    pub session_ttl_secs: u64,
+    /// Tolerance for `Issued At` / `Not Before` in the wallet's clock.
+    pub max_clock_skew_secs: u64,
+    pub cookie_name: String,
+    /// Only send the session cookie over HTTPS.
+    pub cookie_secure: bool,
+    /// What a signed-in wallet may do on routes guarded by `auth`. Anyone
+    /// can sign in with a fresh wallet, so anything above `read-only` opens
+    /// those routes to the public.
+    pub session_role: Role,
+}
+
+impl Default for SiwsConfig {
+    fn default() -> Self {
+        Self {
+            domain: "superdev.dhruvdeora.com".to_string(),
+            nonce_ttl_secs: 300,
+            session_ttl_secs: 24 * 60 * 60,
+            max_clock_skew_secs: 60,
+            cookie_name: "siws_session".to_string(),
+            cookie_secure: true,
+            session_role: Role::ReadOnly,
+        }
+    }
+}
+
+/// Route groups that can be switched off per deployment.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct EndpointsConfig {
+    /// `/hello`
+    pub hello: bool,
+    /// `/airdrop`
+    pub airdrop: bool,
+    /// `/balance`
+    pub balance: bool,
+    /// `/keypair`
+    pub keypair: bool,
+    /// `/token/create`, `/token/mint`
+    pub token: bool,
+    /// `/message/sign`, `/message/verify`
+    pub message: bool,
+    /// `/send/sol`, `/send/token`
+    pub send: bool,
+    /// `/auth/nonce`, `/auth/login`, `/auth/logout`, `/auth/session`
+    pub siws: bool,
+}
+
+impl Default for EndpointsConfig {
+    fn default() -> Self {
+        Self {
+            hello: true,
+            airdrop: true,
+            balance: true,
+            keypair: true,
+            token: true,
+            message: true,
+            send: true,
+            siws: true,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file (if any), apply env overrides and validate.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match env::var("CONFIG_PATH") {
+            Ok(path) => Self::from_file(PathBuf::from(path))?,
+            Err(_) if fs::metadata(DEFAULT_CONFIG_PATH).is_ok() => {
+                Self::from_file(PathBuf::from(DEFAULT_CONFIG_PATH))?
+            }
+            Err(_) => Config::default(),
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: PathBuf) -> Result<Self, ConfigError> {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(source) => return Err(ConfigError::Read { path, source }),
+        };
+        match toml::from_str::<Config>(&contents) {
+            Ok(config) => Ok(Config { source: Some(path), ..config }),
+            Err(source) => Err(ConfigError::Parse { path, source }),
+        }
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        if let Ok(bind) = env::var("BIND_ADDR") {
+            self.server.bind = bind.parse().map_err(|e| ConfigError::Invalid {
+                key: "BIND_ADDR".to_string(),
+                reason: format!("{}", e),
+            })?;
+        }
+        if let Ok(limit) = env::var("BODY_LIMIT_BYTES") {
+            self.server.body_limit = limit.parse().map_err(|e| ConfigError::Invalid {
+                key: "BODY_LIMIT_BYTES".to_string(),
+                reason: format!("{}", e),
+            })?;
+        }
+        if let Ok(prefix) = env::var("API_PREFIX") {
+            self.server.api_prefix = prefix;
+        }
+        if let Ok(dir) = env::var("FRONTEND_DIST_DIR") {
+            self.frontend.dist_dir = PathBuf::from(dir);
+        }
+        if let Ok(url) = env::var("FRONTEND_DEV_SERVER_URL") {
+            self.frontend.dev_server_url = Some(url);
+        }
+        if let Ok(origins) = env::var("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|o| !o.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Ok(credentials) = env::var("CORS_ALLOW_CREDENTIALS") {
+            self.cors.allow_credentials = credentials.parse().map_err(|e| ConfigError::Invalid {
+                key: "CORS_ALLOW_CREDENTIALS".to_string(),
+                reason: format!("{}", e),
+            })?;
+        }
+        if let Ok(cluster) = env::var("SOLANA_CLUSTER") {
+            self.rpc.default_cluster = cluster;
+        }
+        // The single-URL variables predate named clusters; they now target
+        // the default cluster.
+        if let Some(cluster) = self.rpc.clusters.get_mut(&self.rpc.default_cluster) {
+            if let Ok(url) = env::var("SOLANA_RPC_URL") {
+                cluster.url = url;
+            }
+            if let Ok(url) = env::var("SOLANA_WS_URL") {
+                cluster.ws_url = Some(url);
+            }
+        }
+        if let Ok(commitment) = env::var("SOLANA_COMMITMENT") {
+            self.rpc.commitment = commitment.parse().map_err(|reason| ConfigError::Invalid {
+                key: "SOLANA_COMMITMENT".to_string(),
+                reason,
+            })?;
+        }
+        if let Ok(timeout) = env::var("SOLANA_RPC_TIMEOUT_SECS") {
+            self.rpc.timeout_secs = timeout.parse().map_err(|e| ConfigError::Invalid {
+                key: "SOLANA_RPC_TIMEOUT_SECS".to_string(),
+                reason: format!("{}", e),
+            })?;
+        }
+        if let Ok(bind) = env::var("METRICS_ADMIN_BIND") {
+            self.metrics.admin_bind = Some(bind.parse().map_err(|e| ConfigError::Invalid {
+                key: "METRICS_ADMIN_BIND".to_string(),
+                reason: format!("{}", e),
+            })?);
+        }
+        if let Ok(timeout) = env::var("SHUTDOWN_DRAIN_TIMEOUT_SECS") {
+            self.shutdown.drain_timeout_secs = timeout.parse().map_err(|e| ConfigError::Invalid {
+                key: "SHUTDOWN_DRAIN_TIMEOUT_SECS".to_string(),
+                reason: format!("{}", e),
+            })?;
+        }
+        if let Ok(enabled) = env::var("RATE_LIMIT_ENABLED") {
+            self.rate_limit.enabled = enabled.parse().map_err(|e| ConfigError::Invalid {
+                key: "RATE_LIMIT_ENABLED".to_string(),
+                reason: format!("{}", e),
+            })?;
+        }
+        if let Ok(proxies) = env::var("RATE_LIMIT_TRUSTED_PROXIES") {
+            self.rate_limit.trusted_proxies = proxies
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Ok(enabled) = env::var("AUTH_ENABLED") {
+            self.auth.enabled = enabled.parse().map_err(|e| ConfigError::Invalid {
+                key: "AUTH_ENABLED".to_string(),
+                reason: format!("{}", e),
+            })?;
+        }
+        if let Ok(path) = env::var("API_KEYS_FILE") {
+            self.auth.keys_file = PathBuf::from(path);
+        }
+        if let Ok(domain) = env::var("SIWS_DOMAIN") {
+            self.siws.domain = domain;
+        }
+        if let Ok(format) = env::var("LOG_FORMAT") {
+            self.logging.format = format.parse().map_err(|reason| ConfigError::Invalid {
+                key: "LOG_FORMAT".to_string(),
+                reason,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn validate_cors(&self) -> Result<(), ConfigError> {
+        let cors = &self.cors;
+        let invalid = |key: &str, reason: String| ConfigError::Invalid {
+            key: format!("cors.{}", key),
+            reason,
+        };
+        for origin in &cors.allowed_origins {
+            match OriginRule::parse(origin) {
+                Ok(OriginRule::Any) if cors.allow_credentials => {
+                    return Err(invalid(
+                        "allowed_origins",
+                        "'*' cannot be combined with allow_credentials; list the origins instead".to_string(),
+                    ));
+                }
+                Ok(_) => {}
+                Err(reason) => return Err(invalid("allowed_origins", reason)),
+            }
+        }
+        for (key, headers) in [("allowed_headers", &cors.allowed_headers), ("exposed_headers", &cors.exposed_headers)] {
+            for name in headers {
+                if name != "*" && name.parse::<HeaderName>().is_err() {
+                    return Err(invalid(key, format!("'{}' is not a valid header name", name)));
+                }
+            }
+        }
+        if cors.exposed_headers.iter().any(|name| name == "*") {
+            return Err(invalid("exposed_headers", "'*' is not supported; list the headers".to_string()));
+        }
+        for method in &cors.allowed_methods {
+            if method != "*" && method.parse::<Method>().is_err() {
+                return Err(invalid("allowed_methods", format!("'{}' is not a valid method", method)));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_auth(&self) -> Result<(), ConfigError> {
+        let auth = &self.auth;
+        if auth.max_clock_skew_secs == 0 {
+            return Err(ConfigError::Invalid {
+                key: "auth.max_clock_skew_secs".to_string(),
+                reason: "must be greater than 0".to_string(),
+            });
+        }
+        let mut seen = HashSet::new();
+        for signer in &auth.signers {
+            let pubkey = signer.pubkey.parse::<Pubkey>().map_err(|_| ConfigError::Invalid {
+                key: "auth.signers".to_string(),
+                reason: format!("'{}' is not a valid public key", signer.pubkey),
+            })?;
+            if !seen.insert(pubkey) {
+                return Err(ConfigError::Invalid {
+                    key: "auth.signers".to_string(),
+                    reason: format!("{} is listed more than once", signer.pubkey),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_rate_limit(&self) -> Result<(), ConfigError> {
+        let rate_limit = &self.rate_limit;
+        let invalid = |key: String, reason: String| ConfigError::Invalid {
+            key: format!("rate_limit.{}", key),
+            reason,
+        };
+        for entry in &rate_limit.trusted_proxies {
+            parse_proxy(entry).map_err(|reason| invalid("trusted_proxies".to_string(), reason))?;
+        }
+        let buckets = std::iter::once(("default".to_string(), &rate_limit.default))
+            .chain(rate_limit.routes.iter().map(|(route, bucket)| (format!("routes.\"{}\"", route), bucket)));
+        for (key, bucket) in buckets {
+            if bucket.requests == 0 || bucket.period_secs == 0 {
+                return Err(invalid(key, "requests and period_secs must be greater than 0".to_string()));
+            }
+        }
+        for route in rate_limit.routes.keys() {
+            if !route.starts_with('/') {
+                return Err(invalid(
+                    format!("routes.\"{}\"", route),
+                    "routes are paths without the API prefix, e.g. '/airdrop'".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.server.body_limit == 0 {
+            return Err(ConfigError::Invalid {
+                key: "server.body_limit".to_string(),
+                reason: "must be greater than 0".to_string(),
+            });
+        }
+        let prefix = &self.server.api_prefix;
+        if !prefix.starts_with('/') || prefix.len() < 2 || prefix.ends_with('/') {
+            return Err(ConfigError::Invalid {
+                key: "server.api_prefix".to_string(),
+                reason: format!("'{}' must start with '/' and not end with one, e.g. '/api'", prefix),
+            });
+        }
+        if let Some(url) = &self.frontend.dev_server_url {
+            let valid = url.starts_with("http://")
+                && url
+                    .parse::<axum::http::Uri>()
+                    .is_ok_and(|uri| uri.authority().is_some() && uri.path() == "/");
+            if !valid {
+                return Err(ConfigError::Invalid {
+                    key: "frontend.dev_server_url".to_string(),
+                    reason: format!("'{}' must be a plain http:// URL without a path, e.g. 'http://localhost:5173'", url),
+                });
+            }
+        }
+        self.validate_cors()?;
+        self.validate_rate_limit()?;
+        self.validate_auth()?;
+        if self.siws.domain.is_empty() || self.siws.domain.contains(char::is_whitespace) {
+            return Err(ConfigError::Invalid {
+                key: "siws.domain".to_string(),
+                reason: format!("'{}' is not a domain", self.siws.domain),
+            });
+        }
+        let cookie_name = &self.siws.cookie_name;
+        if cookie_name.is_empty() || !cookie_name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-') {
+            return Err(ConfigError::Invalid {
+                key: "siws.cookie_name".to_string(),
+                reason: "use letters, digits, '_' and '-' only".to_string(),
+            });
+        }
+        if self.siws.nonce_ttl_secs == 0 || self.siws.session_ttl_secs == 0 {
+            return Err(ConfigError::Invalid {
+                key: "siws".to_string(),
+                reason: "nonce_ttl_secs and session_ttl_secs must be greater than 0".to_string(),
+            });
+        }
+        if self.siws.session_role == Role::Admin {
+            return Err(ConfigError::Invalid {
+                key: "siws.session_role".to_string(),
+                reason: "wallet sessions cannot be admin; use an API key".to_string(),
+            });
+        }
+        if self.metrics.admin_bind == Some(self.server.bind) {
+            return Err(ConfigError::Invalid {
+                key: "metrics.admin_bind".to_string(),
+                reason: "must differ from server.bind".to_string(),
+            });
+        }
+        if self.rpc.timeout_secs == 0 {
+            return Err(ConfigError::Invalid {
+                key: "rpc.timeout_secs".to_string(),
+                reason: "must be greater than 0".to_string(),
+            });
+        }
+        if !self.rpc.clusters.contains_key(&self.rpc.default_cluster) {
+            return Err(ConfigError::Invalid {
+                key: "rpc.default_cluster".to_string(),
+                reason: format!("'{}' is not one of the configured clusters", self.rpc.default_cluster),
+            });
+        }
+        if self.rpc.health_check_interval_secs == 0 {
+            return Err(ConfigError::Invalid {
+                key: "rpc.health_check_interval_secs".to_string(),
+                reason: "must be greater than 0".to_string(),
+            });
+        }
+        for (name, cluster) in &self.rpc.clusters {
+            for url in cluster.urls() {
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    return Err(ConfigError::Invalid {
+                        key: format!("rpc.clusters.{}.url", name),
+                        reason: format!("'{}' must be an http(s) URL", url),
+                    });
+                }
+            }
+            let ws_url = cluster.ws_url();
+            if !(ws_url.starts_with("ws://") || ws_url.starts_with("wss://")) {
+                return Err(ConfigError::Invalid {
+                    key: format!("rpc.clusters.{}.ws_url", name),
+                    reason: format!("'{}' must be a ws(s) URL", ws_url),
+                });
+            }
+        }
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.filter) {
+            return Err(ConfigError::Invalid {
+                key: "logging.filter".to_string(),
+                reason: e.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
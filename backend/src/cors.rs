@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+use axum::http::{request::Parts, HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+use crate::config::CorsConfig;
+
+/// One entry of `cors.allowed_origins`.
+pub enum OriginRule {
+    /// `*`: any origin. Not allowed together with credentials.
+    Any,
+    /// `https://app.example.com`
+    Exact(String),
+    /// `https://*.example.com`: any subdomain of `example.com`, at any depth,
+    /// but not `example.com` itself.
+    Subdomain {
+        /// `https://`
+        scheme: String,
+        /// `.example.com`, including the port if one was given.
+        suffix: String,
+    },
+}
+
+impl OriginRule {
+    pub fn parse(origin: &str) -> Result<Self, String> {
+        if origin == "*" {
+            return Ok(OriginRule::Any);
+        }
+        let Some((scheme, host)) = origin.split_once("://") else {
+            return Err(format!("'{}' is not an http(s) origin", origin));
+        };
+        if !(scheme == "http" || scheme == "https") || origin.parse::<HeaderValue>().is_err() {
+            return Err(format!("'{}' is not an http(s) origin", origin));
+        }
+        if host.is_empty() || host.contains('/') {
+            return Err(format!("'{}' must not have a path", origin));
+        }
+        match host.strip_prefix("*.") {
+            Some(domain) if !domain.is_empty() && !domain.contains('*') => Ok(OriginRule::Subdomain {
+                scheme: format!("{}://", scheme),
+                suffix: format!(".{}", domain.to_ascii_lowercase()),
+            }),
+            _ if host.contains('*') => Err(format!(
+                "'{}': '*' is only allowed as the leftmost label, e.g. 'https://*.example.com'",
+                origin
+            )),
+            _ => Ok(OriginRule::Exact(origin.to_ascii_lowercase())),
+        }
+    }
+
+    fn matches(&self, origin: &str) -> bool {
+        match self {
+            OriginRule::Any => true,
+            OriginRule::Exact(exact) => origin.eq_ignore_ascii_case(exact),
+            OriginRule::Subdomain { scheme, suffix } => {
+                let origin = origin.to_ascii_lowercase();
+                origin
+                    .strip_prefix(scheme.as_str())
+                    .and_then(|host| host.strip_suffix(suffix.as_str()))
+                    .is_some_and(|sub| {
+                        !sub.is_empty()
+                            && sub.split('.').all(|label| {
+                                !label.is_empty() && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                            })
+                    })
+            }
+        }
+    }
+}
+
+/// Build the CORS layer described by `config`, which was validated by
+/// `Config::load`.
+///
+/// A `*` header or method list allows anything; with credentials on, the
+/// browser does not honour a literal `*`, so the request's own preflight
+/// headers are echoed back instead.
+pub fn layer(config: &CorsConfig) -> CorsLayer {
+    let rules: Vec<OriginRule> = config
+        .allowed_origins
+        .iter()
+        .map(|origin| OriginRule::parse(origin).expect("validated CORS origin"))
+        .collect();
+    let allow_origin = if rules.iter().any(|rule| matches!(rule, OriginRule::Any)) {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::predicate(move |origin: &HeaderValue, _: &Parts| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| rules.iter().any(|rule| rule.matches(origin)))
+        })
+    };
+
+    let allow_headers = if is_wildcard(&config.allowed_headers) {
+        if config.allow_credentials {
+            AllowHeaders::mirror_request()
+        } else {
+            AllowHeaders::any()
+        }
+    } else {
+        AllowHeaders::list(
+            config
+                .allowed_headers
+                .iter()
+                .map(|h| h.parse::<HeaderName>().expect("validated CORS header")),
+        )
+    };
+
+    let allow_methods = if is_wildcard(&config.allowed_methods) {
+        if config.allow_credentials {
+            AllowMethods::mirror_request()
+        } else {
+            AllowMethods::any()
+        }
+    } else {
+        AllowMethods::list(
+            config
+                .allowed_methods
+                .iter()
+                .map(|m| m.parse::<Method>().expect("validated CORS method")),
+        )
+    };
+
+    let mut layer = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_headers(allow_headers)
+        .allow_methods(allow_methods)
+        .expose_headers(
+            config
+                .exposed_headers
+                .iter()
+                .map(|h| h.parse::<HeaderName>().expect("validated CORS header"))
+                .collect::<Vec<_>>(),
+        )
+        .allow_credentials(config.allow_credentials);
+    if let Some(secs) = config.max_age_secs {
+        layer = layer.max_age(Duration::from_secs(secs));
+    }
+    layer
+}
+
+fn is_wildcard(list: &[String]) -> bool {
+    list.iter().any(|item| item == "*")
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn rule(origin: &str) -> OriginRule {
+        OriginRule::parse(origin).unwrap()
+    }
+
+    #[test]
+    fn exact_origin_matches_case_insensitively() {
+        let rule = rule("https://app.example.com");
+        assert!(rule.matches("https://app.example.com"));
+        assert!(rule.matches("HTTPS://App.Example.com"));
+        assert!(!rule.matches("http://app.example.com"));
+        assert!(!rule.matches("https://app.example.com:8443"));
+    }
+
+    #[test]
+    fn subdomain_pattern_needs_a_subdomain() {
+        let rule = rule("https://*.example.com");
+        assert!(rule.matches("https://a.example.com"));
+        assert!(rule.matches("https://a.b.example.com"));
+        assert!(!rule.matches("https://example.com"));
+        assert!(!rule.matches("https://evil-example.com"));
+        assert!(!rule.matches("https://.example.com"));
+        assert!(!rule.matches("http://a.example.com"));
+    }
+
+    #[test]
+    fn wildcard_only_allowed_as_leftmost_label() {
+        assert!(OriginRule::parse("https://a.*.example.com").is_err());
+        assert!(OriginRule::parse("https://app*.example.com").is_err());
+        assert!(OriginRule::parse("https://*.").is_err());
+        assert!(OriginRule::parse("ftp://example.com").is_err());
+        assert!(OriginRule::parse("https://example.com/path").is_err());
+        assert!(matches!(rule("*"), OriginRule::Any));
+    }
+
+    #[tokio::test]
+    async fn preflight_with_credentials_echoes_request() {
+        let config = CorsConfig {
+            allowed_origins: vec!["https://*.example.com".to_string()],
+            allowed_headers: vec!["*".to_string()],
+            allowed_methods: vec!["*".to_string()],
+            exposed_headers: Vec::new(),
+            allow_credentials: true,
+            max_age_secs: Some(600),
+        };
+        let app = Router::new().route("/send/sol", post(|| async {})).layer(layer(&config));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/send/sol")
+                    .header(header::ORIGIN, "https://app.example.com")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type,x-solana-cluster")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_METHODS], "POST");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS], "content-type,x-solana-cluster");
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+    }
+
+    #[tokio::test]
+    async fn preflight_from_unlisted_origin_gets_no_allow_origin() {
+        let app = Router::new()
+            .route("/send/sol", post(|| async {}))
+            .layer(layer(&CorsConfig::default()));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/send/sol")
+                    .header(header::ORIGIN, "https://evil.example.org")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(!response.headers().contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+}
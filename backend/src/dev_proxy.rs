@@ -0,0 +1,128 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{
+        header::{self, HeaderName},
+        uri::{PathAndQuery, Uri},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Router,
+};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::{TokioExecutor, TokioIo},
+};
+use tracing::{debug, warn};
+
+/// Headers that describe a single connection and must not be forwarded.
+/// `connection` and `upgrade` are kept on upgrade requests so the HMR
+/// WebSocket handshake reaches Vite intact.
+const HOP_BY_HOP: [&str; 6] = [
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+];
+
+#[derive(Clone)]
+struct DevProxy {
+    /// Scheme and authority of the dev server, e.g. `http://localhost:5173`.
+    upstream: Uri,
+    client: Client<HttpConnector, Body>,
+}
+
+/// Forward every request that reaches it to the Vite dev server at
+/// `upstream`, including WebSocket upgrades used for hot module reload, so
+/// the browser only ever talks to the backend's origin.
+///
+/// Meant to be merged into the app in place of the built SPA; API, health
+/// and metrics routes still take precedence. `upstream` was validated by
+/// `Config::load`.
+pub fn router<S>(upstream: &str) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let proxy = DevProxy {
+        upstream: upstream.parse().expect("validated dev server URL"),
+        client: Client::builder(TokioExecutor::new()).build_http(),
+    };
+    Router::new().fallback(forward).with_state(proxy)
+}
+
+async fn forward(State(proxy): State<DevProxy>, mut request: Request) -> Response {
+    let path = request
+        .uri()
+        .path_and_query()
+        .cloned()
+        .unwrap_or_else(|| PathAndQuery::from_static("/"));
+    let mut uri = proxy.upstream.clone().into_parts();
+    uri.path_and_query = Some(path);
+    *request.uri_mut() = Uri::from_parts(uri).expect("upstream has scheme and authority");
+
+    let upgrade = is_upgrade(request.headers());
+    strip_hop_by_hop(request.headers_mut(), upgrade);
+    // Vite rejects hosts it does not know; present ourselves as the address
+    // it is listening on.
+    if let Some(host) = proxy.upstream.authority().and_then(|a| HeaderValue::from_str(a.as_str()).ok())
+        && let Some(original) = request.headers_mut().insert(header::HOST, host)
+    {
+        request.headers_mut().insert(HeaderName::from_static("x-forwarded-host"), original);
+    }
+
+    let client_upgrade = upgrade.then(|| hyper::upgrade::on(&mut request));
+
+    let mut response = match proxy.client.request(request).await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!(upstream = %proxy.upstream, error = %e, "dev server unreachable");
+            return (StatusCode::BAD_GATEWAY, format!("Vite dev server at {} is unreachable: {}", proxy.upstream, e))
+                .into_response();
+        }
+    };
+
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        if let Some(client_upgrade) = client_upgrade {
+            let server_upgrade = hyper::upgrade::on(&mut response);
+            tokio::spawn(async move {
+                let (client, server) = match tokio::try_join!(client_upgrade, server_upgrade) {
+                    Ok(pair) => pair,
+                    Err(e) => {
+                        warn!(error = %e, "dev server upgrade failed");
+                        return;
+                    }
+                };
+                let (mut client, mut server) = (TokioIo::new(client), TokioIo::new(server));
+                if let Err(e) = tokio::io::copy_bidirectional(&mut client, &mut server).await {
+                    debug!(error = %e, "dev server tunnel closed");
+                }
+            });
+        }
+    } else {
+        strip_hop_by_hop(response.headers_mut(), false);
+    }
+
+    response.map(Body::new)
+}
+
+fn is_upgrade(headers: &HeaderMap) -> bool {
+    headers.contains_key(header::UPGRADE)
+        && headers
+            .get_all(header::CONNECTION)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|token| token.trim().eq_ignore_ascii_case("upgrade"))
+}
+
+fn strip_hop_by_hop(headers: &mut HeaderMap, upgrade: bool) {
+    for name in HOP_BY_HOP {
+        headers.remove(name);
+    }
+    if !upgrade {
+        headers.remove(header::CONNECTION);
+        headers.remove(header::UPGRADE);
+    }
+}
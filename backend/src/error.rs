@@ -0,0 +1,237 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json as ResponseJson, Response},
+};
+use serde::Serialize;
+use solana_client::client_error::ClientError;
+
+use crate::auth::Role;
+use crate::metrics::ErrorCode;
+use crate::rpc_pool::is_transport_error;
+use crate::ApiResponse;
+
+/// Structured error object carried in the `error` field of [`ApiResponse`].
+///
+/// `code` is stable and meant for clients to branch on; `message` is human
+/// readable and may change between releases.
+#[derive(Serialize, Debug)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Individual field errors when `code` is `VALIDATION_FAILED`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ErrorBody>,
+}
+
+/// Every failure a handler can return.
+#[derive(thiserror::Error, Debug)]
+pub enum ApiError {
+    #[error("Missing required field: {field}")]
+    MissingField { field: &'static str },
+
+    #[error("Invalid '{field}' public key: {reason}")]
+    InvalidPubkey { field: &'static str, reason: String },
+
+    #[error("Invalid {field}: {reason}")]
+    InvalidAmount { field: &'static str, reason: String },
+
+    #[error("Invalid {field}: must be between 0 and 9")]
+    InvalidDecimals { field: &'static str },
+
+    #[error("Invalid {field}: {reason}")]
+    InvalidSecretKey { field: &'static str, reason: String },
+
+    #[error("Invalid {field}: {reason}")]
+    InvalidSignature { field: &'static str, reason: String },
+
+    #[error("Invalid {field}: {reason}")]
+    InvalidExtension { field: &'static str, reason: String },
+
+    #[error("{reason}")]
+    InvalidAccount { field: &'static str, reason: String },
+
+    #[error("{} field(s) failed validation", .0.len())]
+    Validation(Vec<ApiError>),
+
+    #[error("Missing required field: {path}")]
+    MissingBodyField { path: String },
+
+    #[error("Invalid value at '{path}': {reason}")]
+    InvalidType { path: String, reason: String },
+
+    #[error("Malformed JSON: {0}")]
+    MalformedJson(String),
+
+    #[error("Invalid request body: {0}")]
+    InvalidBody(String),
+
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+
+    #[error("{0}")]
+    PayloadTooLarge(String),
+
+    #[error("No API route for {method} {path}")]
+    RouteNotFound { method: String, path: String },
+
+    #[error("Invalid query string: {reason}")]
+    InvalidQuery { path: Option<String>, reason: String },
+
+    #[error("Unknown cluster '{name}'")]
+    UnknownCluster { name: String },
+
+    #[error("Unauthorized: {reason}")]
+    Unauthorized { reason: String },
+
+    #[error("This route needs the {required} role, the caller has {actual}")]
+    InsufficientRole { required: Role, actual: Role },
+
+    #[error("Invalid sign-in message: {reason}")]
+    InvalidSignInMessage { reason: String },
+
+    #[error("Sign-in rejected: {reason}")]
+    SignInRejected { reason: String },
+
+    #[error("Airdrops are not available on cluster '{cluster}'")]
+    AirdropUnsupported { cluster: String },
+
+    #[error("Too many requests, retry in {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+
+    #[error("Failed to build instruction: {0}")]
+    InstructionFailed(String),
+
+    #[error("RPC node unavailable: {0}")]
+    RpcUnavailable(String),
+
+    #[error("RPC request failed: {0}")]
+    Rpc(String),
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::MissingField { .. } | ApiError::MissingBodyField { .. } => "MISSING_FIELD",
+            ApiError::InvalidPubkey { .. } => "INVALID_PUBKEY",
+            ApiError::InvalidAmount { .. } => "INVALID_AMOUNT",
+            ApiError::InvalidDecimals { .. } => "INVALID_DECIMALS",
+            ApiError::InvalidSecretKey { .. } => "INVALID_SECRET_KEY",
+            ApiError::InvalidSignature { .. } => "INVALID_SIGNATURE",
+            ApiError::InvalidExtension { .. } => "INVALID_EXTENSION",
+            ApiError::InvalidAccount { .. } => "INVALID_ACCOUNT",
+            ApiError::Validation(_) => "VALIDATION_FAILED",
+            ApiError::InvalidType { .. } => "INVALID_TYPE",
+            ApiError::MalformedJson(_) => "MALFORMED_JSON",
+            ApiError::InvalidBody(_) => "INVALID_BODY",
+            ApiError::UnsupportedMediaType(_) => "UNSUPPORTED_MEDIA_TYPE",
+            ApiError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            ApiError::RouteNotFound { .. } => "ROUTE_NOT_FOUND",
+            ApiError::InvalidQuery { .. } => "INVALID_QUERY",
+            ApiError::UnknownCluster { .. } => "UNKNOWN_CLUSTER",
+            ApiError::Unauthorized { .. } => "UNAUTHORIZED",
+            ApiError::InsufficientRole { .. } => "INSUFFICIENT_ROLE",
+            ApiError::InvalidSignInMessage { .. } => "INVALID_SIGN_IN_MESSAGE",
+            ApiError::SignInRejected { .. } => "SIGN_IN_REJECTED",
+            ApiError::AirdropUnsupported { .. } => "AIRDROP_UNSUPPORTED",
+            ApiError::RateLimited { .. } => "RATE_LIMITED",
+            ApiError::InstructionFailed(_) => "INSTRUCTION_FAILED",
+            ApiError::RpcUnavailable(_) => "RPC_UNAVAILABLE",
+            ApiError::Rpc(_) => "RPC_ERROR",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::MissingField { .. }
+            | ApiError::InvalidPubkey { .. }
+            | ApiError::InvalidAmount { .. }
+            | ApiError::InvalidDecimals { .. }
+            | ApiError::InvalidSecretKey { .. }
+            | ApiError::InvalidSignature { .. }
+            | ApiError::InvalidExtension { .. }
+            | ApiError::InvalidAccount { .. }
+            | ApiError::Validation(_)
+            | ApiError::MissingBodyField { .. }
+            | ApiError::MalformedJson(_)
+            | ApiError::InvalidBody(_)
+            | ApiError::InvalidQuery { .. }
+            | ApiError::UnknownCluster { .. }
+            | ApiError::InvalidSignInMessage { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized { .. } | ApiError::SignInRejected { .. } => StatusCode::UNAUTHORIZED,
+            ApiError::InsufficientRole { .. } | ApiError::AirdropUnsupported { .. } => StatusCode::FORBIDDEN,
+            ApiError::RouteNotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::InvalidType { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::InstructionFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::RpcUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Rpc(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    /// Name (or JSON path, for body errors) of the request field that caused
+    /// the error, if any.
+    pub fn field(&self) -> Option<String> {
+        match self {
+            ApiError::MissingField { field }
+            | ApiError::InvalidPubkey { field, .. }
+            | ApiError::InvalidAmount { field, .. }
+            | ApiError::InvalidDecimals { field }
+            | ApiError::InvalidSecretKey { field, .. }
+            | ApiError::InvalidSignature { field, .. }
+            | ApiError::InvalidExtension { field, .. }
+            | ApiError::InvalidAccount { field, .. } => Some(field.to_string()),
+            ApiError::MissingBodyField { path } | ApiError::InvalidType { path, .. } => Some(path.clone()),
+            ApiError::InvalidQuery { path, .. } => path.clone(),
+            ApiError::UnknownCluster { .. } => Some("cluster".to_string()),
+            ApiError::InvalidSignInMessage { .. } => Some("message".to_string()),
+            ApiError::Validation(_)
+            | ApiError::MalformedJson(_)
+            | ApiError::InvalidBody(_)
+            | ApiError::UnsupportedMediaType(_)
+            | ApiError::PayloadTooLarge(_)
+            | ApiError::RouteNotFound { .. }
+            | ApiError::Unauthorized { .. }
+            | ApiError::InsufficientRole { .. }
+            | ApiError::SignInRejected { .. }
+            | ApiError::AirdropUnsupported { .. }
+            | ApiError::RateLimited { .. }
+            | ApiError::InstructionFailed(_)
+            | ApiError::RpcUnavailable(_)
+            | ApiError::Rpc(_) => None,
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        ErrorBody {
+            code: self.code(),
+            message: self.to_string(),
+            field: self.field(),
+            errors: match self {
+                ApiError::Validation(errors) => errors.iter().map(ApiError::body).collect(),
+                _ => Vec::new(),
+            },
+        }
+    }
+}
+
+impl From<ClientError> for ApiError {
+    fn from(err: ClientError) -> Self {
+        if is_transport_error(&err) {
+            ApiError::RpcUnavailable(err.to_string())
+        } else {
+            ApiError::Rpc(err.to_string())
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status(), ResponseJson(ApiResponse::<()>::error(self.body()))).into_response();
+        response.extensions_mut().insert(ErrorCode(self.code()));
+        response
+    }
+}
@@ -0,0 +1,216 @@
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+};
+use serde::de::DeserializeOwned;
+
+use crate::error::ApiError;
+
+/// Drop-in replacement for [`axum::Json`] whose rejections are reported
+/// through [`ApiError`] instead of axum's plain-text bodies.
+pub struct ApiJson<T>(pub T);
+
+/// Drop-in replacement for [`axum::extract::Query`] whose rejections are
+/// reported through [`ApiError`].
+pub struct ApiQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_json_content_type(req.headers()) {
+            return Err(ApiError::UnsupportedMediaType(
+                "Expected request with `Content-Type: application/json`".to_string(),
+            ));
+        }
+
+        let bytes = Bytes::from_request(req, state).await.map_err(|rejection| {
+            if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+                ApiError::PayloadTooLarge(rejection.body_text())
+            } else {
+                ApiError::InvalidBody(rejection.body_text())
+            }
+        })?;
+
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        match serde_path_to_error::deserialize(deserializer) {
+            Ok(value) => Ok(ApiJson(value)),
+            Err(err) => Err(json_error(err)),
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ApiQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer = serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        match serde_path_to_error::deserialize(deserializer) {
+            Ok(value) => Ok(ApiQuery(value)),
+            Err(err) => {
+                let path = error_path(err.path());
+                let reason = err.into_inner().to_string();
+                match missing_field(&reason) {
+                    Some(field) => Err(ApiError::MissingBodyField { path: join_path(path, field) }),
+                    None => Err(ApiError::InvalidQuery { path, reason }),
+                }
+            }
+        }
+    }
+}
+
+fn has_json_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    essence == "application/json" || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+fn json_error(err: serde_path_to_error::Error<serde_json::Error>) -> ApiError {
+    let path = error_path(err.path());
+    let inner = err.into_inner();
+    match inner.classify() {
+        serde_json::error::Category::Data => {
+            let reason = inner.to_string();
+            match missing_field(&reason) {
+                Some(field) => ApiError::MissingBodyField { path: join_path(path, field) },
+                None => ApiError::InvalidType {
+                    path: path.unwrap_or_else(|| ".".to_string()),
+                    reason,
+                },
+            }
+        }
+        serde_json::error::Category::Syntax
+        | serde_json::error::Category::Eof
+        | serde_json::error::Category::Io => ApiError::MalformedJson(inner.to_string()),
+    }
+}
+
+/// `serde_path_to_error` renders the root as `.`; treat that as no path.
+fn error_path(path: &serde_path_to_error::Path) -> Option<String> {
+    let rendered = path.to_string();
+    (rendered != ".").then_some(rendered)
+}
+
+/// serde reports a missing field against its parent, with the field name
+/// only in the message (``missing field `mint` ``). Pull it back out so the
+/// client gets the full path.
+fn missing_field(message: &str) -> Option<&str> {
+    let rest = message.strip_prefix("missing field `")?;
+    rest.split('`').next()
+}
+
+fn join_path(parent: Option<String>, field: &str) -> String {
+    match parent {
+        Some(parent) => format!("{}.{}", parent, field),
+        None => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::TokenCreateRequest;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Page {
+        limit: u32,
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/token/create", post(|_: ApiJson<TokenCreateRequest>| async { "ok" }))
+            .route("/page", post(|_: ApiQuery<Page>| async { "ok" }))
+    }
+
+    /// Status and JSON body of the response.
+    async fn send(uri: &str, content_type: Option<&str>, body: &str) -> (StatusCode, Value) {
+        let mut request = Request::post(uri);
+        if let Some(content_type) = content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+        let response = app().oneshot(request.body(Body::from(body.to_string())).unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    fn assert_error(body: &Value, code: &str, field: Option<&str>) {
+        assert_eq!(body["success"], false, "{body}");
+        assert_eq!(body["error"]["code"], code, "{body}");
+        assert_eq!(body["error"]["field"].as_str(), field, "{body}");
+        assert!(body["error"]["message"].is_string(), "{body}");
+    }
+
+    #[tokio::test]
+    async fn nested_type_mismatch_reports_its_path() {
+        let body = json!({
+            "mintAuthority": "B2UY29wPcL71jigJiM4hp45tEnhXJRnimYAZEWy8JV6J",
+            "decimals": 6,
+            "tokenProgram": "token-2022",
+            "extensions": { "transferFee": { "basisPoints": "fifty", "maxFee": 5_000 } },
+        });
+        let (status, body) = send("/token/create", Some("application/json"), &body.to_string()).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_error(&body, "INVALID_TYPE", Some("extensions.transferFee.basisPoints"));
+    }
+
+    #[tokio::test]
+    async fn missing_nested_field_reports_its_path() {
+        let body = json!({
+            "mintAuthority": "B2UY29wPcL71jigJiM4hp45tEnhXJRnimYAZEWy8JV6J",
+            "decimals": 6,
+            "extensions": { "transferFee": { "basisPoints": 50 } },
+        });
+        let (status, body) = send("/token/create", Some("application/json"), &body.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_error(&body, "MISSING_FIELD", Some("extensions.transferFee.maxFee"));
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_rejected() {
+        let (status, body) = send("/token/create", Some("application/json"), r#"{"decimals": 6,"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_error(&body, "MALFORMED_JSON", None);
+    }
+
+    #[tokio::test]
+    async fn missing_content_type_is_rejected() {
+        for content_type in [None, Some("text/plain")] {
+            let (status, body) = send("/token/create", content_type, r#"{"decimals": 6}"#).await;
+            assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            assert_error(&body, "UNSUPPORTED_MEDIA_TYPE", None);
+        }
+    }
+
+    #[tokio::test]
+    async fn bad_query_is_rejected() {
+        let (status, body) = send("/page?limit=many", None, "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_error(&body, "INVALID_QUERY", Some("limit"));
+
+        let (status, body) = send("/page", None, "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_error(&body, "MISSING_FIELD", Some("limit"));
+    }
+}
@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use axum::{
+    http::{header, HeaderValue, Response},
+    Router,
+};
+use tower_http::{
+    services::{ServeDir, ServeFile},
+    set_header::SetResponseHeaderLayer,
+};
+use tracing::info;
+#[cfg(not(feature = "embed-frontend"))]
+use tracing::warn;
+
+use crate::config::FrontendConfig;
+
+/// Vite puts content-hashed files under `assets/`, so they never change
+/// under the same name.
+const IMMUTABLE: HeaderValue = HeaderValue::from_static("public, max-age=31536000, immutable");
+/// Everything else (notably `index.html`) must be revalidated so a deploy is
+/// picked up on the next load.
+const NO_CACHE: HeaderValue = HeaderValue::from_static("no-cache");
+
+/// Routes serving the SPA compiled into the binary. `dist_dir` is ignored.
+///
+/// Meant to be merged into the app after the API is nested, so API routes
+/// take precedence over the fallback.
+#[cfg(feature = "embed-frontend")]
+pub fn router<S>(_config: &FrontendConfig) -> Option<Router<S>>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    info!("serving embedded frontend");
+    Some(Router::new().fallback(embedded::serve))
+}
+
+/// Routes serving the SPA from `dist_dir`, or `None` when there is nothing
+/// to serve.
+///
+/// Meant to be merged into the app after the API is nested, so API routes
+/// take precedence over the fallback.
+#[cfg(not(feature = "embed-frontend"))]
+pub fn router<S>(config: &FrontendConfig) -> Option<Router<S>>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let dist_dir = &config.dist_dir;
+    if !dist_dir.join("index.html").is_file() {
+        warn!(dist_dir = %dist_dir.display(), "no index.html in frontend dist dir, not serving the SPA");
+        return None;
+    }
+    info!(dist_dir = %dist_dir.display(), "serving frontend");
+    Some(disk_router(dist_dir))
+}
+
+/// Hashed assets under `/assets` with long-lived caching, other files at the
+/// root, and `index.html` for any unknown path so client-side routes survive
+/// a reload. `.br` / `.gz` siblings are served when the client accepts them.
+#[cfg_attr(feature = "embed-frontend", allow(dead_code))]
+fn disk_router<S>(dist_dir: &Path) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let index = ServeFile::new(dist_dir.join("index.html"))
+        .precompressed_br()
+        .precompressed_gzip();
+
+    // A missing asset is a real 404, not a client-side route.
+    let assets = ServeDir::new(dist_dir.join("assets"))
+        .precompressed_br()
+        .precompressed_gzip();
+
+    let root = ServeDir::new(dist_dir)
+        .precompressed_br()
+        .precompressed_gzip()
+        .fallback(index);
+
+    let assets = Router::new()
+        .nest_service("/assets", assets)
+        .layer(SetResponseHeaderLayer::overriding(header::CACHE_CONTROL, |res: &Response<_>| {
+            res.status().is_success().then_some(IMMUTABLE)
+        }));
+
+    let root = Router::new()
+        .fallback_service(root)
+        .layer(SetResponseHeaderLayer::overriding(header::CACHE_CONTROL, NO_CACHE));
+
+    assets.merge(root)
+}
+
+/// Same behaviour as [`disk_router`], served from memory. ETags are derived
+/// from the content hash rust-embed computes at build time.
+#[cfg(feature = "embed-frontend")]
+mod embedded {
+    use axum::{
+        body::Body,
+        http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri},
+        response::{IntoResponse, Response},
+    };
+    use rust_embed::{EmbeddedFile, RustEmbed};
+
+    use super::{IMMUTABLE, NO_CACHE};
+
+    #[derive(RustEmbed)]
+    #[folder = "dist/"]
+    struct Dist;
+
+    /// Precompressed variants, in order of preference.
+    const ENCODINGS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+    pub async fn serve(method: Method, uri: Uri, request_headers: HeaderMap) -> Response {
+        if method != Method::GET && method != Method::HEAD {
+            return (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, "GET,HEAD")]).into_response();
+        }
+
+        let path = match uri.path().trim_start_matches('/') {
+            "" => "index.html",
+            path => path,
+        };
+        let (path, file) = match Dist::get(path) {
+            Some(file) => (path, file),
+            // A missing asset is a real 404, not a client-side route.
+            None if path.starts_with("assets/") => return StatusCode::NOT_FOUND.into_response(),
+            None => match Dist::get("index.html") {
+                Some(file) => ("index.html", file),
+                None => return StatusCode::NOT_FOUND.into_response(),
+            },
+        };
+
+        let mut headers = HeaderMap::new();
+        if let Ok(mime) = HeaderValue::from_str(file.metadata.mimetype()) {
+            headers.insert(header::CONTENT_TYPE, mime);
+        }
+        let cache_control = if path.starts_with("assets/") { IMMUTABLE } else { NO_CACHE };
+        headers.insert(header::CACHE_CONTROL, cache_control);
+        headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+
+        let (encoding, file) = ENCODINGS
+            .iter()
+            .filter(|(encoding, _)| accepts(&request_headers, encoding))
+            .find_map(|(encoding, ext)| Some((Some(*encoding), Dist::get(&format!("{}.{}", path, ext))?)))
+            .unwrap_or((None, file));
+        if let Some(encoding) = encoding {
+            headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        }
+
+        let etag = etag(&file);
+        headers.insert(header::ETAG, etag.clone());
+        let not_modified = request_headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|tag| tag.trim() == "*" || tag.trim() == etag);
+        if not_modified {
+            return (StatusCode::NOT_MODIFIED, headers).into_response();
+        }
+
+        (headers, Body::from(file.data)).into_response()
+    }
+
+    fn etag(file: &EmbeddedFile) -> HeaderValue {
+        let hash = file.metadata.sha256_hash();
+        HeaderValue::from_str(&format!("\"{}\"", hex::encode(&hash[..16]))).unwrap()
+    }
+
+    /// Whether `Accept-Encoding` lists `encoding` without `q=0`.
+    fn accepts(headers: &HeaderMap, encoding: &str) -> bool {
+        headers
+            .get_all(header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|entry| {
+                let mut parts = entry.split(';').map(str::trim);
+                parts.next() == Some(encoding)
+                    && !parts.any(|param| matches!(param, "q=0" | "q=0.0" | "q=0.00" | "q=0.000"))
+            })
+    }
+}
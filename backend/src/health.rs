@@ -0,0 +1,98 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json as ResponseJson, Response},
+};
+use serde::Serialize;
+
+use crate::rpc_pool::NodeStatus;
+use crate::state::AppState;
+
+/// A cluster counts as stale once its background health check has missed
+/// this many intervals.
+const STALE_CHECK_INTERVALS: u32 = 3;
+
+#[derive(Serialize)]
+pub struct LivenessReport {
+    status: &'static str,
+}
+
+#[derive(Serialize)]
+struct ReadinessReport {
+    ready: bool,
+    /// Shutting down: finish what is in flight but send no new traffic.
+    draining: bool,
+    /// File the running config was loaded from, or `defaults`.
+    config_source: String,
+    checks: ReadinessChecks,
+}
+
+#[derive(Serialize)]
+struct ReadinessChecks {
+    rpc: BTreeMap<String, ClusterCheck>,
+}
+
+#[derive(Serialize)]
+struct ClusterCheck {
+    ok: bool,
+    /// Only the default cluster gates readiness; the others are reported
+    /// for visibility.
+    default: bool,
+    /// The background health check ran recently.
+    fresh: bool,
+    /// At least one node answered `getHealth` / `getSlot`.
+    reachable: bool,
+    /// At least one reachable node is within `rpc.max_slot_lag` of the best.
+    in_sync: bool,
+    last_check_secs_ago: Option<u64>,
+    nodes: Vec<NodeStatus>,
+}
+
+/// `GET /healthz`: the process is up and serving requests.
+pub async fn healthz() -> ResponseJson<LivenessReport> {
+    ResponseJson(LivenessReport { status: "ok" })
+}
+
+/// `GET /readyz`: 200 when the service can do useful work, 503 otherwise.
+/// Uses the state recorded by the RPC pools' background health checks
+/// rather than probing on every call.
+pub async fn readyz(State(state): State<AppState>) -> Response {
+    let stale_after = state.config.rpc.health_check_interval() * STALE_CHECK_INTERVALS;
+    let rpc: BTreeMap<String, ClusterCheck> = state
+        .clusters
+        .iter()
+        .map(|cluster| {
+            let age = cluster.rpc.last_check().map(|at| at.elapsed());
+            let nodes = cluster.rpc.status();
+            let fresh = age.is_some_and(|age| age <= stale_after);
+            let reachable = nodes.iter().any(|n| n.slot > 0);
+            let in_sync = nodes.iter().any(|n| n.healthy && n.slot > 0);
+            let check = ClusterCheck {
+                ok: fresh && reachable && in_sync,
+                default: cluster.name == state.clusters.default_name(),
+                fresh,
+                reachable,
+                in_sync,
+                last_check_secs_ago: age.as_ref().map(Duration::as_secs),
+                nodes,
+            };
+            (cluster.name.clone(), check)
+        })
+        .collect();
+
+    let draining = state.shutdown.is_cancelled();
+    let ready = !draining && rpc.values().filter(|c| c.default).all(|c| c.ok);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let report = ReadinessReport {
+        ready,
+        draining,
+        config_source: match &state.config.source {
+            Some(path) => path.display().to_string(),
+            None => "defaults".to_string(),
+        },
+        checks: ReadinessChecks { rpc },
+    };
+    (status, ResponseJson(report)).into_response()
+}
@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::auth::Role;
+
+const KEY_PREFIX: &str = "sk";
+
+#[derive(thiserror::Error, Debug)]
+pub enum KeyStoreError {
+    #[error("failed to read key file {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+
+    #[error("failed to parse key file {path}: {source}")]
+    Parse { path: PathBuf, source: serde_json::Error },
+
+    #[error("failed to write key file {path}: {source}")]
+    Write { path: PathBuf, source: io::Error },
+
+    #[error("no key with id '{0}'")]
+    UnknownKey(String),
+}
+
+/// One issued key. Only the hash of the key is kept; the key itself is shown
+/// once, when it is created.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KeyRecord {
+    /// Public identifier, embedded in the key and used to revoke it.
+    pub id: String,
+    /// Hex SHA-256 of the full key.
+    pub hash: String,
+    pub role: Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Unix seconds.
+    pub created_at: u64,
+    /// Unix seconds; revoked keys are kept for the audit trail.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct KeyFile {
+    keys: Vec<KeyRecord>,
+}
+
+/// Whether `token` has the shape of a key issued by [`create`].
+pub fn is_api_key(token: &str) -> bool {
+    token.strip_prefix(KEY_PREFIX).is_some_and(|rest| rest.starts_with('_'))
+}
+
+/// Hex SHA-256 of `key`, the form keys are stored and looked up in.
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn read_file(path: &Path) -> Result<KeyFile, KeyStoreError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(KeyFile::default()),
+        Err(source) => return Err(KeyStoreError::Read { path: path.to_path_buf(), source }),
+    };
+    serde_json::from_str(&contents).map_err(|source| KeyStoreError::Parse { path: path.to_path_buf(), source })
+}
+
+/// Replace the file in one step so a server reloading it never sees half a
+/// write. Keys are only readable by the owner.
+fn write_file(path: &Path, file: &KeyFile) -> Result<(), KeyStoreError> {
+    let write = || -> io::Result<()> {
+        let tmp = path.with_extension("tmp");
+        let json = serde_json::to_vec_pretty(file).map_err(io::Error::other)?;
+        // A leftover tmp file may have looser permissions; the mode only
+        // applies to a file this call creates.
+        match fs::remove_file(&tmp) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut out = options.open(&tmp)?;
+        out.write_all(&json)?;
+        out.sync_all()?;
+        drop(out);
+        fs::rename(&tmp, path)
+    };
+    write().map_err(|source| KeyStoreError::Write { path: path.to_path_buf(), source })
+}
+
+/// Active keys, loaded from a JSON file.
+///
+/// The server only reads the file; keys are issued and revoked with the
+/// `keys` subcommand, and [`KeyStore::spawn_reloader`] picks the changes up.
+pub struct KeyStore {
+    path: PathBuf,
+    /// Active keys by hash.
+    keys: RwLock<HashMap<String, KeyRecord>>,
+    modified: RwLock<Option<SystemTime>>,
+}
+
+impl KeyStore {
+    /// A missing file is an empty store.
+    pub fn open(path: &Path) -> Result<Self, KeyStoreError> {
+        let store = Self {
+            path: path.to_path_buf(),
+            keys: RwLock::new(HashMap::new()),
+            modified: RwLock::new(None),
+        };
+        store.reload()?;
+        Ok(store)
+    }
+
+    pub fn reload(&self) -> Result<(), KeyStoreError> {
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        let keys = read_file(&self.path)?
+            .keys
+            .into_iter()
+            .filter(|key| key.revoked_at.is_none())
+            .map(|key| (key.hash.clone(), key))
+            .collect();
+        *self.keys.write().unwrap() = keys;
+        *self.modified.write().unwrap() = modified;
+        Ok(())
+    }
+
+    /// The active record for `key`, if any.
+    pub fn lookup(&self, key: &str) -> Option<KeyRecord> {
+        self.keys.read().unwrap().get(&hash_key(key)).cloned()
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.keys.read().unwrap().len()
+    }
+
+    /// Reload the file every `interval` when it has changed, until
+    /// `shutdown` is cancelled. A file that fails to parse leaves the
+    /// previous keys in place.
+    pub fn spawn_reloader(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.cancelled() => break,
+                }
+                let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+                if modified == *self.modified.read().unwrap() {
+                    continue;
+                }
+                match self.reload() {
+                    Ok(()) => tracing::info!(path = %self.path.display(), keys = self.active_count(), "API keys reloaded"),
+                    Err(e) => tracing::error!(error = %e, "failed to reload API keys, keeping the previous set"),
+                }
+            }
+        })
+    }
+}
+
+/// Issue a key with `role`, record it in the file at `path` and return the
+/// key, which is not stored anywhere in plain form.
+pub fn create(path: &Path, role: Role, label: Option<String>) -> Result<(KeyRecord, String), KeyStoreError> {
+    let mut file = read_file(path)?;
+
+    let mut id = [0u8; 4];
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut id);
+    OsRng.fill_bytes(&mut secret);
+    let id = hex::encode(id);
+    let key = format!("{}_{}_{}", KEY_PREFIX, id, bs58::encode(secret).into_string());
+
+    let record = KeyRecord {
+        id,
+        hash: hash_key(&key),
+        role,
+        label,
+        created_at: now(),
+        revoked_at: None,
+    };
+    file.keys.push(record.clone());
+    write_file(path, &file)?;
+    Ok((record, key))
+}
+
+/// Mark the key with `id` revoked.
+pub fn revoke(path: &Path, id: &str) -> Result<KeyRecord, KeyStoreError> {
+    let mut file = read_file(path)?;
+    let record = file
+        .keys
+        .iter_mut()
+        .find(|key| key.id == id)
+        .ok_or_else(|| KeyStoreError::UnknownKey(id.to_string()))?;
+    record.revoked_at.get_or_insert_with(now);
+    let record = record.clone();
+    write_file(path, &file)?;
+    Ok(record)
+}
+
+/// Every key in the file, revoked ones included.
+pub fn list(path: &Path) -> Result<Vec<KeyRecord>, KeyStoreError> {
+    Ok(read_file(path)?.keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("key-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("api_keys.json")
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn key_file_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = key_file("mode");
+        // A stale tmp file left world-readable must not carry its mode over
+        fs::write(path.with_extension("tmp"), "{}").unwrap();
+        fs::set_permissions(path.with_extension("tmp"), fs::Permissions::from_mode(0o644)).unwrap();
+
+        create(&path, Role::Builder, None).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn revoked_keys_stop_resolving() {
+        let path = key_file("revoke");
+        let (record, key) = create(&path, Role::Signer, Some("ci".to_string())).unwrap();
+        let store = KeyStore::open(&path).unwrap();
+        assert_eq!(store.lookup(&key).unwrap().role, Role::Signer);
+
+        revoke(&path, &record.id).unwrap();
+        store.reload().unwrap();
+        assert!(store.lookup(&key).is_none());
+        assert!(list(&path).unwrap()[0].revoked_at.is_some());
+    }
+}
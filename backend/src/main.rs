@@ -1,33 +1,64 @@
+mod auth;
+mod cli;
+mod cluster;
+mod config;
+mod cors;
+mod dev_proxy;
+mod error;
+mod extract;
+mod frontend;
+mod health;
+mod key_store;
+mod metrics;
+mod rate_limit;
+mod rpc_pool;
+mod secret;
+mod shutdown;
+mod signed_request;
+mod siws;
+mod state;
+mod telemetry;
+mod token_extensions;
+mod token_program;
+mod transaction;
+mod validation;
+
 use axum::{
     routing::{get, post},
     Router,
-    http::{Method, StatusCode},
-    Json,
+    http::Method,
+    extract::OriginalUri,
     response::Json as ResponseJson,
-    extract::Query,
-};
-use tower_http::{
-    services::ServeDir,
-    cors::{CorsLayer},
+    extract::DefaultBodyLimit,
+    middleware,
 };
-use std::net::SocketAddr;
-use solana_client::nonblocking::rpc_client::RpcClient;
+use std::{future::IntoFuture, net::SocketAddr, time::Duration};
 use solana_sdk::{
     signer::{keypair::Keypair, Signer},
     signature::Signature,
     native_token::LAMPORTS_PER_SOL,
-    commitment_config::CommitmentConfig,
     pubkey::Pubkey,
     instruction::Instruction,
-    sysvar,
     system_instruction,
     system_program,
 };
-use spl_token::{instruction::initialize_mint2, ID as TOKEN_PROGRAM_ID};
+use spl_token_2022::instruction::{initialize_mint2, mint_to, mint_to_checked, transfer_checked};
 use serde::{Deserialize, Serialize};
-use base64;
-use spl_associated_token_account::get_associated_token_address;
-use spl_token::instruction::transfer_checked;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::cluster::{LazyCluster, SelectedCluster};
+use crate::config::Config;
+use crate::error::{ApiError, ErrorBody};
+use crate::extract::{ApiJson, ApiQuery};
+use crate::secret::Secret;
+use crate::state::AppState;
+use crate::token_extensions::{MintExtensions, MintExtensionsRequest};
+use crate::token_program::TokenProgram;
+use crate::transaction::{TransactionData, TransactionRequest};
+use tracing::{debug, info, warn};
+use zeroize::Zeroizing;
+use crate::validation::{Validate, Validator};
 
 #[derive(Serialize)]
 struct ApiResponse<T> {
@@ -35,7 +66,7 @@ struct ApiResponse<T> {
     #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
+    error: Option<ErrorBody>,
 }
 
 impl<T> ApiResponse<T> {
@@ -47,25 +78,35 @@ impl<T> ApiResponse<T> {
         }
     }
 
-    fn error(message: String) -> Self {
+    fn error(error: ErrorBody) -> Self {
         Self {
             success: false,
             data: None,
-            error: Some(message),
+            error: Some(error),
         }
     }
 }
 
+type ApiResult<T> = Result<ResponseJson<ApiResponse<T>>, ApiError>;
+
+/// How often fully refilled rate limit buckets are dropped.
+const RATE_LIMIT_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the API key file is checked for changes made by `backend keys`.
+const KEY_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Serialize)]
 struct AirdropData {
     account_id: String,
     airdrop_signature: String,
+    cluster: String,
 }
 
 #[derive(Serialize)]
 struct BalanceData {
     balance: u64,
     public_key: String,
+    cluster: String,
 }
 
 #[derive(Serialize)]
@@ -86,15 +127,30 @@ struct BalanceQuery {
 #[derive(Serialize)]
 struct KeypairData {
     pubkey: String,
-    secret: String,
+    secret: Secret,
 }
 
 #[derive(Deserialize, Debug)]
 struct TokenCreateRequest {
     #[serde(rename = "mintAuthority")]
-    mintAuthority: String,
-    mint: String,
+    mint_authority: String,
+    /// A fresh mint keypair is generated when omitted.
+    mint: Option<String>,
+    /// Funds the mint account's rent. Defaults to the mint authority.
+    payer: Option<String>,
+    /// Omitted: the mint authority can also freeze. `null`: the mint is not
+    /// freezable.
+    #[serde(rename = "freezeAuthority", default, deserialize_with = "deserialize_present")]
+    freeze_authority: Option<Option<String>>,
     decimals: u8,
+    /// Defaults to the original SPL Token program.
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<TokenProgram>,
+    /// Token-2022 mint extensions.
+    #[serde(default)]
+    extensions: MintExtensionsRequest,
+    /// Also return the instructions as an unsigned transaction.
+    transaction: Option<TransactionRequest>,
 }
 
 #[derive(Serialize)]
@@ -105,16 +161,55 @@ struct Account {
 }
 
 #[derive(Serialize)]
-struct TokenCreateData {
+struct InstructionData {
     program_id: String,
     accounts: Vec<Account>,
     instruction_data: String,
 }
 
+impl From<&Instruction> for InstructionData {
+    fn from(ix: &Instruction) -> Self {
+        Self {
+            program_id: ix.program_id.to_string(),
+            accounts: ix.accounts.iter().map(|acc| Account {
+                pubkey: acc.pubkey.to_string(),
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            }).collect(),
+            instruction_data: BASE64.encode(&ix.data),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TokenCreateData {
+    /// Cluster the rent and blockhash were fetched from.
+    cluster: String,
+    mint: String,
+    /// Only set when the server generated the mint keypair; it must co-sign
+    /// the `create_account` instruction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mint_secret: Option<Secret>,
+    /// `None` when the mint cannot be frozen.
+    freeze_authority: Option<String>,
+    /// Size of the mint account, including any extensions.
+    space: u64,
+    rent_lamports: u64,
+    /// `create_account`, then any extension inits, then `initialize_mint2`,
+    /// in execution order.
+    instructions: Vec<InstructionData>,
+    /// The `initialize_mint2` instruction on its own, as returned before the
+    /// full sequence was added.
+    #[serde(flatten)]
+    initialize_mint: InstructionData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction: Option<TransactionData>,
+}
+
 #[derive(Deserialize, Debug)]
 struct MessageSignRequest {
     message: Option<String>,
-    secret: Option<String>,
+    secret: Option<Secret>,
 }
 
 #[derive(Serialize)]
@@ -143,13 +238,20 @@ struct SendSolRequest {
     from: Option<String>,
     to: Option<String>,
     lamports: Option<u64>,
+    /// Also return the instruction as an unsigned transaction.
+    transaction: Option<TransactionRequest>,
 }
 
 #[derive(Serialize)]
 struct SendSolData {
+    /// Set when the cluster was queried, i.e. for a `transaction`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cluster: Option<String>,
     program_id: String,
     accounts: Vec<String>,
     instruction_data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction: Option<TransactionData>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -159,6 +261,11 @@ struct SendTokenRequest {
     owner: Option<String>,
     amount: Option<u64>,
     decimals: Option<u8>,
+    /// Detected from the mint account when omitted.
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<TokenProgram>,
+    /// Also return the instruction as an unsigned transaction.
+    transaction: Option<TransactionRequest>,
 }
 
 #[derive(Serialize)]
@@ -170,32 +277,56 @@ struct TokenAccount {
 
 #[derive(Serialize)]
 struct SendTokenData {
+    /// Set when the cluster was queried for the token program or a
+    /// `transaction`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cluster: Option<String>,
     program_id: String,
     accounts: Vec<TokenAccount>,
     instruction_data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction: Option<TransactionData>,
 }
 
 #[derive(Deserialize, Debug)]
 struct TokenMintRequest {
     mint: String,
     #[serde(rename = "mintAuthority")]
-    mintAuthority: String,
+    mint_authority: String,
     #[serde(rename = "tokenAccount")]
     token_account: String,
     amount: u64,
+    /// When given, the instruction is `MintToChecked` and the token program
+    /// rejects it unless the mint has these decimals.
     decimals: Option<u8>,
+    /// Detected from the mint account when omitted.
+    #[serde(rename = "tokenProgram")]
+    token_program: Option<TokenProgram>,
+    /// Also return the instruction as an unsigned transaction.
+    transaction: Option<TransactionRequest>,
 }
 
 #[derive(Serialize)]
 struct TokenMintData {
+    /// Set when the cluster was queried for the token program or a
+    /// `transaction`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cluster: Option<String>,
     program_id: String,
     accounts: Vec<Account>,
     instruction_data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction: Option<TransactionData>,
 }
 
-fn get_rpc_url() -> String {
-    std::env::var("SOLANA_RPC_URL")
-        .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string())
+/// Deserialize a field that is present (even as `null`) into `Some`, so an
+/// `Option<Option<T>>` with `#[serde(default)]` can tell absent from `null`.
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 /// Parse a public key from multiple possible formats:
@@ -242,322 +373,579 @@ fn parse_pubkey_flexible(input: &str) -> Result<Pubkey, String> {
     }
 }
 
-#[tokio::main]
-async fn main() {
-    // Load environment variables from .env file
-    dotenv::dotenv().ok();
-    
-    let cors = CorsLayer::new()
-        // allow requests from exactly our dev server
-        .allow_origin("https://superdev.dhruvdeora.com".parse::<axum::http::HeaderValue>().unwrap())
-        // or use Any if you really need wildcard
-        // .allow_origin(Any)
-
-        // allow common methods (OPTIONS is implicit for pre-flight)
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-
-        // allow these request headers
-        .allow_headers([axum::http::header::CONTENT_TYPE]);
-
-    let api = Router::new()
-        .route("/hello", get(hello))
-        .route("/airdrop", get(get_airdrop))
-        .route("/balance", get(get_balance_query).post(post_balance))
-        .route("/keypair", post(get_keypair))
-        .route("/token/create", post(create_token))
-        .route("/token/mint", post(mint_token))
-        .route("/message/sign", post(sign_message))
-        .route("/message/verify", post(verify_message))
-        .route("/send/sol", post(send_sol))
-        .route("/send/token", post(send_token));
-
-    let static_files = || ServeDir::new("../dist")
-        .append_index_html_on_directories(true);
-
-    let app = Router::new()
-        .nest("/", api)
-        // .nest_service("/", static_files())
-        .layer(cors);
-
-    let addr: SocketAddr = ([0, 0, 0, 0], 8001).into();
-    println!("Listening on http://{}", addr);
+impl Validate for BalanceRequest {
+    type Output = Pubkey;
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    fn validate(&self) -> Result<Pubkey, ApiError> {
+        let mut v = Validator::new();
+        let pubkey = v.pubkey("public_key", Some(&self.public_key));
+        v.finish(|| pubkey)
+    }
 }
 
-async fn hello() -> Result<ResponseJson<ApiResponse<HelloData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("GET /hello");
-    Ok(ResponseJson(ApiResponse::success(HelloData {
-        message: "Hello from Axum!".to_string(),
-    })))
+impl Validate for BalanceQuery {
+    type Output = Pubkey;
+
+    fn validate(&self) -> Result<Pubkey, ApiError> {
+        let mut v = Validator::new();
+        let pubkey = v.pubkey("public_key", Some(&self.public_key));
+        v.finish(|| pubkey)
+    }
 }
 
-async fn get_airdrop() -> Result<ResponseJson<ApiResponse<AirdropData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("GET /airdrop");
-    let keypair = Keypair::new();
-    let pubkey = keypair.pubkey();
-    
-    let client = RpcClient::new_with_commitment(
-        get_rpc_url(),
-        CommitmentConfig::confirmed(),
-    );
-    
-    let airdrop_amount = LAMPORTS_PER_SOL;
-    
-    match client.request_airdrop(&pubkey, airdrop_amount).await {
-        Ok(signature) => {
-            Ok(ResponseJson(ApiResponse::success(AirdropData {
-                account_id: pubkey.to_string(),
-                airdrop_signature: signature.to_string(),
-            })))
-        }
-        Err(e) => {
-            Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Airdrop failed: {}", e)))
-            ))
-        }
+struct TokenCreateParams {
+    mint_authority: Pubkey,
+    /// `None` when the server should generate the mint keypair.
+    mint: Option<Pubkey>,
+    payer: Pubkey,
+    freeze_authority: Option<Pubkey>,
+    decimals: u8,
+    extensions: MintExtensions,
+}
+
+impl Validate for TokenCreateRequest {
+    type Output = TokenCreateParams;
+
+    fn validate(&self) -> Result<TokenCreateParams, ApiError> {
+        let mut v = Validator::new();
+        let mint_authority = v.pubkey("mintAuthority", Some(&self.mint_authority));
+        let mint = self.mint.as_deref().map(|mint| v.pubkey("mint", Some(mint)));
+        let payer = self.payer.as_deref().map(|payer| v.pubkey("payer", Some(payer)));
+        let freeze_authority = match &self.freeze_authority {
+            None => mint_authority.map(Some),
+            Some(None) => Some(None),
+            Some(Some(freeze_authority)) => v.pubkey("freezeAuthority", Some(freeze_authority)).map(Some),
+        };
+        let decimals = v.decimals("decimals", self.decimals);
+        let extensions = self.extensions.validate_with(&mut v);
+        v.check(self.extensions.is_empty() || self.token_program == Some(TokenProgram::Token2022), || {
+            ApiError::InvalidExtension {
+                field: "extensions",
+                reason: "mint extensions need tokenProgram \"token-2022\"".to_string(),
+            }
+        });
+        v.check(!self.extensions.frozen_by_default() || !matches!(self.freeze_authority, Some(None)), || {
+            ApiError::InvalidExtension {
+                field: "extensions.defaultAccountState",
+                reason: "frozen accounts need a freeze authority".to_string(),
+            }
+        });
+        v.finish(|| Some(TokenCreateParams {
+            mint_authority: mint_authority?,
+            mint: match mint {
+                Some(mint) => Some(mint?),
+                None => None,
+            },
+            payer: payer.unwrap_or(mint_authority)?,
+            freeze_authority: freeze_authority?,
+            decimals: decimals?,
+            extensions: extensions?,
+        }))
     }
 }
 
-async fn get_balance_query(Query(params): Query<BalanceQuery>) -> Result<ResponseJson<ApiResponse<BalanceData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("GET /balance params: {:?}", params);
-    let client = RpcClient::new(get_rpc_url());
-    
-    if params.public_key.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-        ));
+struct TokenMintParams {
+    mint: Pubkey,
+    mint_authority: Pubkey,
+    token_account: Pubkey,
+    amount: u64,
+    decimals: Option<u8>,
+}
+
+impl Validate for TokenMintRequest {
+    type Output = TokenMintParams;
+
+    fn validate(&self) -> Result<TokenMintParams, ApiError> {
+        let mut v = Validator::new();
+        let mint = v.pubkey("mint", Some(&self.mint));
+        let mint_authority = v.pubkey("mintAuthority", Some(&self.mint_authority));
+        let token_account = v.pubkey("tokenAccount", Some(&self.token_account));
+        // Raw token units: the whole u64 range is valid
+        v.check(self.amount > 0, || ApiError::InvalidAmount {
+            field: "amount",
+            reason: "must be greater than 0".to_string(),
+        });
+        let decimals = match self.decimals {
+            None => Some(None),
+            Some(decimals) => v.decimals("decimals", decimals).map(Some),
+        };
+        v.finish(|| Some(TokenMintParams {
+            mint: mint?,
+            mint_authority: mint_authority?,
+            token_account: token_account?,
+            amount: self.amount,
+            decimals: decimals?,
+        }))
     }
-    
-    let pubkey = match parse_pubkey_flexible(&params.public_key) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid public key: {}", e)))
-            ));
-        }
-    };
+}
 
-    match client.get_balance(&pubkey).await {
-        Ok(balance) => {
-            Ok(ResponseJson(ApiResponse::success(BalanceData { 
-                balance,
-                public_key: params.public_key,
-            })))
-        }
-        Err(e) => {
-            Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Failed to get balance: {}", e)))
-            ))
-        }
+struct MessageSignParams {
+    message: String,
+    keypair: Keypair,
+}
+
+impl Validate for MessageSignRequest {
+    type Output = MessageSignParams;
+
+    fn validate(&self) -> Result<MessageSignParams, ApiError> {
+        let mut v = Validator::new();
+        let message = v.required("message", self.message.as_deref());
+        let keypair = v.required("secret", self.secret.as_ref().map(Secret::expose)).and_then(|secret| {
+            let keypair = bs58::decode(secret)
+                .into_vec()
+                .map(Zeroizing::new)
+                .map_err(|_| "not valid base58")
+                .and_then(|bytes| Keypair::from_bytes(&bytes).map_err(|_| "not a valid ed25519 keypair"));
+            match keypair {
+                Ok(keypair) => Some(keypair),
+                Err(reason) => {
+                    v.push(ApiError::InvalidSecretKey { field: "secret", reason: reason.to_string() });
+                    None
+                }
+            }
+        });
+        v.finish(|| Some(MessageSignParams {
+            message: message?.to_string(),
+            keypair: keypair?,
+        }))
     }
 }
 
-async fn post_balance(Json(payload): Json<BalanceRequest>) -> Result<ResponseJson<ApiResponse<BalanceData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("POST /balance payload: {:?}", payload);
-    let client = RpcClient::new(get_rpc_url());
-    
-    if payload.public_key.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-        ));
+struct MessageVerifyParams {
+    message: String,
+    signature: Signature,
+    pubkey: Pubkey,
+}
+
+impl Validate for MessageVerifyRequest {
+    type Output = MessageVerifyParams;
+
+    fn validate(&self) -> Result<MessageVerifyParams, ApiError> {
+        let mut v = Validator::new();
+        let message = v.required("message", self.message.as_deref());
+        let signature = v.signature("signature", self.signature.as_deref());
+        let pubkey = v.pubkey("pubkey", self.pubkey.as_deref());
+        v.finish(|| Some(MessageVerifyParams {
+            message: message?.to_string(),
+            signature: signature?,
+            pubkey: pubkey?,
+        }))
     }
-    
-    let pubkey = match parse_pubkey_flexible(&payload.public_key) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid public key: {}", e)))
-            ));
-        }
-    };
+}
+
+struct SendSolParams {
+    from: Pubkey,
+    to: Pubkey,
+    lamports: u64,
+}
+
+impl Validate for SendSolRequest {
+    type Output = SendSolParams;
+
+    fn validate(&self) -> Result<SendSolParams, ApiError> {
+        const MAX_REASONABLE_LAMPORTS: u64 = 1_000_000_000 * LAMPORTS_PER_SOL; // 1 billion SOL
 
-    match client.get_balance(&pubkey).await {
-        Ok(balance) => {
-            Ok(ResponseJson(ApiResponse::success(BalanceData { 
-                balance,
-                public_key: payload.public_key,
-            })))
+        let mut v = Validator::new();
+        let from = v.pubkey("from", self.from.as_deref());
+        let to = v.pubkey("to", self.to.as_deref());
+        let lamports = v.amount("lamports", self.lamports);
+
+        let zero_pubkey = Pubkey::new_from_array([0u8; 32]);
+        if let Some(from) = from {
+            v.check(from != zero_pubkey, || ApiError::InvalidAccount {
+                field: "from",
+                reason: "Cannot send from zero address".to_string(),
+            });
         }
-        Err(e) => {
-            Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Failed to get balance: {}", e)))
-            ))
+        if let Some(to) = to {
+            v.check(to != zero_pubkey, || ApiError::InvalidAccount {
+                field: "to",
+                reason: "Cannot send to zero address".to_string(),
+            });
+            v.check(to != system_program::ID, || ApiError::InvalidAccount {
+                field: "to",
+                reason: "Cannot send SOL to system program address".to_string(),
+            });
+        }
+        if let (Some(from), Some(to)) = (from, to) {
+            v.check(from != to, || ApiError::InvalidAccount {
+                field: "to",
+                reason: "Cannot send SOL to the same address".to_string(),
+            });
         }
+        if let Some(lamports) = lamports {
+            v.check(lamports <= MAX_REASONABLE_LAMPORTS, || ApiError::InvalidAmount {
+                field: "lamports",
+                reason: "exceeds reasonable limits".to_string(),
+            });
+        }
+
+        v.finish(|| Some(SendSolParams {
+            from: from?,
+            to: to?,
+            lamports: lamports?,
+        }))
     }
 }
 
-async fn get_keypair() -> Result<ResponseJson<ApiResponse<KeypairData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("POST /keypair");
-    let keypair = Keypair::new();
-    let address = keypair.pubkey();
-    
-    let pubkey = address.to_string();
-    
-    let secret_bytes = keypair.to_bytes();
-    let secret = bs58::encode(&secret_bytes).into_string();
-    
-    Ok(ResponseJson(ApiResponse::success(KeypairData {
-        pubkey,
-        secret,
-    })))
+struct SendTokenParams {
+    destination: Pubkey,
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+    decimals: u8,
 }
 
-async fn create_token(Json(payload): Json<TokenCreateRequest>) -> Result<ResponseJson<ApiResponse<TokenCreateData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("POST /token/create payload: {:?}", payload);
-    // Validate that required fields are not empty
-    if payload.mintAuthority.is_empty() || payload.mint.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-        ));
+impl Validate for SendTokenRequest {
+    type Output = SendTokenParams;
+
+    fn validate(&self) -> Result<SendTokenParams, ApiError> {
+        let mut v = Validator::new();
+        let destination = v.pubkey("destination", self.destination.as_deref());
+        let mint = v.pubkey("mint", self.mint.as_deref());
+        let owner = v.pubkey("owner", self.owner.as_deref());
+        let amount = v.amount("amount", self.amount);
+        // Use provided decimals or default to 9 (most common for SPL tokens)
+        let decimals = v.decimals("decimals", self.decimals.unwrap_or(9));
+        v.finish(|| Some(SendTokenParams {
+            destination: destination?,
+            mint: mint?,
+            owner: owner?,
+            amount: amount?,
+            decimals: decimals?,
+        }))
     }
+}
 
-    // Validate decimals range (SPL tokens typically use 0-9 decimals)
-    if payload.decimals > 9 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Invalid decimals: must be between 0 and 9".to_string()))
-        ));
-    }
+#[tokio::main]
+async fn main() {
+    // Load environment variables from .env file
+    dotenv::dotenv().ok();
 
-    // Parse the mint authority and mint pubkeys using flexible format support
-    let mintAuthority = match parse_pubkey_flexible(&payload.mintAuthority) {
-        Ok(pk) => pk,
+    let config = match Config::load() {
+        Ok(config) => config,
         Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid mint authority public key: {}", e)))
-            ));
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
         }
     };
 
-    let mint_pubkey = match parse_pubkey_flexible(&payload.mint) {
-        Ok(pk) => pk,
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&config, &args) {
+        std::process::exit(code);
+    }
+
+    let cors = cors::layer(&config.cors);
+
+    telemetry::init(&config.logging);
+
+    let state = match AppState::new(config) {
+        Ok(state) => state,
         Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid mint public key: {}", e)))
-            ));
+            eprintln!("Failed to load API keys: {}", e);
+            std::process::exit(1);
         }
     };
+    let config = state.config.clone();
+    let shutdown = state.shutdown.clone();
+    shutdown::cancel_on_signal(shutdown.clone());
+    let mut background_tasks = state
+        .clusters
+        .spawn_health_checks(config.rpc.health_check_interval(), &shutdown);
+
+    let endpoints = &config.endpoints;
+    let mut api = Router::new();
+    if endpoints.hello {
+        api = api.route("/hello", get(hello));
+    }
+    if endpoints.airdrop {
+        api = api.route("/airdrop", get(get_airdrop));
+    }
+    if endpoints.balance {
+        api = api.route("/balance", get(get_balance_query).post(post_balance));
+    }
+    if endpoints.keypair {
+        api = api.route("/keypair", post(get_keypair));
+    }
+    if endpoints.token {
+        api = api
+            .route("/token/create", post(create_token))
+            .route("/token/mint", post(mint_token));
+    }
+    if endpoints.message {
+        api = api
+            .route("/message/sign", post(sign_message))
+            .route("/message/verify", post(verify_message));
+    }
+    if endpoints.send {
+        api = api
+            .route("/send/sol", post(send_sol))
+            .route("/send/token", post(send_token));
+    }
+    if endpoints.siws {
+        api = api
+            .route("/auth/nonce", get(siws::nonce))
+            .route("/auth/login", post(siws::login))
+            .route("/auth/logout", post(siws::logout))
+            .route("/auth/session", get(siws::session));
+    }
 
-    let initialize_mint_ix = match initialize_mint2(
-        &TOKEN_PROGRAM_ID,
-        &mint_pubkey,
-        &mintAuthority,
-        Some(&mintAuthority), 
-        payload.decimals,
-    ) {
-        Ok(ix) => ix,
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                ResponseJson(ApiResponse::error(format!("Failed to create mint instruction: {}", e)))
-            ));
+    if config.rate_limit.enabled {
+        api = api.route_layer(middleware::from_fn_with_state(state.clone(), rate_limit::limit_requests));
+        background_tasks.push(
+            state
+                .rate_limiter
+                .clone()
+                .spawn_sweeper(RATE_LIMIT_SWEEP_INTERVAL, shutdown.clone()),
+        );
+    }
+
+    // Added last so it runs first: the rate limiter keys on the API key it finds
+    if let Some(keys) = &state.key_store {
+        api = api.route_layer(middleware::from_fn_with_state(state.clone(), auth::authenticate));
+        background_tasks.push(keys.clone().spawn_reloader(KEY_RELOAD_INTERVAL, shutdown.clone()));
+        let signers = state.request_signers.as_ref().map_or(0, |signers| signers.count());
+        if keys.active_count() == 0 && signers == 0 {
+            warn!(keys_file = %config.auth.keys_file.display(), "auth is on but no API keys or request signers exist; create a key with `backend keys create`");
         }
-    };
+    }
 
-    // Extract accounts from the instruction
-    let accounts: Vec<Account> = initialize_mint_ix.accounts.iter().map(|acc| {
-        Account {
-            pubkey: acc.pubkey.to_string(),
-            is_signer: acc.is_signer,
-            is_writable: acc.is_writable,
+    // Unknown API paths get a JSON error instead of falling through to the SPA
+    let api = api.fallback(api_not_found);
+
+    let mut app = Router::new()
+        .nest(&config.server.api_prefix, api)
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz));
+
+    if config.metrics.enabled {
+        match config.metrics.admin_bind {
+            None => {
+                app = app.route("/metrics", get(metrics::metrics_handler));
+            }
+            Some(admin_addr) => {
+                let admin = Router::new()
+                    .route("/metrics", get(metrics::metrics_handler))
+                    .with_state(state.clone());
+                let listener = tokio::net::TcpListener::bind(admin_addr).await.unwrap();
+                info!(%admin_addr, "serving metrics on admin address");
+                let admin_shutdown = shutdown.clone();
+                tokio::spawn(async move {
+                    axum::serve(listener, admin)
+                        .with_graceful_shutdown(async move { admin_shutdown.cancelled().await })
+                        .await
+                        .unwrap()
+                });
+            }
         }
-    }).collect();
+    }
 
-    let instruction_data = base64::encode(&initialize_mint_ix.data);
+    if let Some(dev_server) = &config.frontend.dev_server_url {
+        warn!(%dev_server, "proxying non-API requests to the frontend dev server");
+        app = app.merge(dev_proxy::router(dev_server));
+    } else if config.frontend.enabled && let Some(spa) = frontend::router(&config.frontend) {
+        app = app.merge(spa);
+    }
 
-    let response_data = TokenCreateData {
-        program_id: TOKEN_PROGRAM_ID.to_string(),
-        accounts,
-        instruction_data,
-    };
+    let app = app
+        .layer(middleware::from_fn_with_state(state.clone(), metrics::track_requests))
+        .layer(DefaultBodyLimit::max(config.server.body_limit))
+        .layer(cors)
+        .with_state(state);
+    let app = telemetry::with_request_tracing(app);
 
-    Ok(ResponseJson(ApiResponse::success(response_data)))
-}
+    let addr = config.server.bind;
+    info!(%addr, "listening");
 
-async fn mint_token(Json(payload): Json<TokenMintRequest>) -> Result<ResponseJson<ApiResponse<TokenMintData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("POST /token/mint payload: {:?}", payload);
-    if payload.mint.is_empty() || payload.mintAuthority.is_empty() || payload.token_account.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-        ));
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    let readiness_delay = config.shutdown.readiness_delay();
+    // Peer addresses are needed to rate limit by client IP
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown::stop_accepting(shutdown.clone(), readiness_delay));
+
+    tokio::select! {
+        result = server.into_future() => result.unwrap(),
+        _ = shutdown::drain_deadline(shutdown.clone(), readiness_delay, config.shutdown.drain_timeout()) => {
+            warn!("drain timeout elapsed, abandoning in-flight requests");
+        }
     }
 
-    if payload.amount == 0 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Invalid amount: must be greater than 0".to_string()))
-        ));
+    futures::future::join_all(background_tasks).await;
+    info!("shutdown complete");
+}
+
+async fn api_not_found(method: Method, OriginalUri(uri): OriginalUri) -> ApiError {
+    ApiError::RouteNotFound {
+        method: method.to_string(),
+        path: uri.path().to_string(),
     }
+}
 
-    let decimals = payload.decimals.unwrap_or(9);
+async fn hello() -> ApiResult<HelloData> {
+    Ok(ResponseJson(ApiResponse::success(HelloData {
+        message: "Hello from Axum!".to_string(),
+    })))
+}
 
-    if decimals > 9 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Invalid decimals: must be between 0 and 9".to_string()))
-        ));
+async fn get_airdrop(SelectedCluster(cluster): SelectedCluster) -> ApiResult<AirdropData> {
+    if !cluster.airdrop {
+        return Err(ApiError::AirdropUnsupported { cluster: cluster.name.clone() });
     }
 
-    let mint_pubkey = match parse_pubkey_flexible(&payload.mint) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid mint public key: {}", e)))
-            ));
-        }
-    };
+    let keypair = Keypair::new();
+    let pubkey = keypair.pubkey();
+    
+    let airdrop_amount = LAMPORTS_PER_SOL;
+    
+    let signature = cluster
+        .rpc
+        .send("requestAirdrop", |rpc| async move { rpc.request_airdrop(&pubkey, airdrop_amount).await })
+        .await?;
+
+    Ok(ResponseJson(ApiResponse::success(AirdropData {
+        account_id: pubkey.to_string(),
+        airdrop_signature: signature.to_string(),
+        cluster: cluster.name.clone(),
+    })))
+}
 
-    let mintAuthority_pubkey = match parse_pubkey_flexible(&payload.mintAuthority) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid mint authority public key: {}", e)))
-            ));
+async fn get_balance_query(SelectedCluster(cluster): SelectedCluster, ApiQuery(params): ApiQuery<BalanceQuery>) -> ApiResult<BalanceData> {
+    debug!(?params, "balance query");
+    let pubkey = params.validate()?;
+
+    let balance = cluster
+        .rpc
+        .read("getBalance", |rpc| async move { rpc.get_balance(&pubkey).await })
+        .await?;
+
+    Ok(ResponseJson(ApiResponse::success(BalanceData { 
+        balance,
+        public_key: params.public_key,
+        cluster: cluster.name.clone(),
+    })))
+}
+
+async fn post_balance(SelectedCluster(cluster): SelectedCluster, ApiJson(payload): ApiJson<BalanceRequest>) -> ApiResult<BalanceData> {
+    debug!(?payload, "request payload");
+    let pubkey = payload.validate()?;
+
+    let balance = cluster
+        .rpc
+        .read("getBalance", |rpc| async move { rpc.get_balance(&pubkey).await })
+        .await?;
+
+    Ok(ResponseJson(ApiResponse::success(BalanceData { 
+        balance,
+        public_key: payload.public_key,
+        cluster: cluster.name.clone(),
+    })))
+}
+
+async fn get_keypair() -> ApiResult<KeypairData> {
+    let keypair = Keypair::new();
+    let address = keypair.pubkey();
+    
+    let pubkey = address.to_string();
+    
+    let secret_bytes = Zeroizing::new(keypair.to_bytes());
+    let secret = Secret::new(bs58::encode(secret_bytes.as_slice()).into_string());
+    
+    Ok(ResponseJson(ApiResponse::success(KeypairData {
+        pubkey,
+        secret,
+    })))
+}
+
+async fn create_token(SelectedCluster(cluster): SelectedCluster, ApiJson(payload): ApiJson<TokenCreateRequest>) -> ApiResult<TokenCreateData> {
+    debug!(?payload, "request payload");
+    let TokenCreateParams { mint_authority, mint, payer, freeze_authority, decimals, extensions } = payload.validate()?;
+    let transaction_params = payload.transaction.as_ref().map(Validate::validate).transpose()?;
+    let token_program = payload.token_program.unwrap_or_default().id();
+
+    let (mint, mint_secret) = match mint {
+        Some(mint) => (mint, None),
+        None => {
+            let keypair = Keypair::new();
+            let secret_bytes = Zeroizing::new(keypair.to_bytes());
+            let secret = Secret::new(bs58::encode(secret_bytes.as_slice()).into_string());
+            (keypair.pubkey(), Some(secret))
         }
     };
 
-    let token_account_pubkey = match parse_pubkey_flexible(&payload.token_account) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid token account public key: {}", e)))
-            ));
-        }
+    let space = extensions.account_len()?;
+    let rent_lamports = cluster
+        .rpc
+        .read("getMinimumBalanceForRentExemption", |rpc| async move {
+            rpc.get_minimum_balance_for_rent_exemption(space).await
+        })
+        .await?;
+
+    let mut instructions = vec![system_instruction::create_account(
+        &payer,
+        &mint,
+        rent_lamports,
+        space as u64,
+        &token_program,
+    )];
+    instructions.extend(extensions.instructions(&token_program, &mint, &mint_authority)?);
+
+    let initialize_mint_ix = initialize_mint2(
+        &token_program,
+        &mint,
+        &mint_authority,
+        freeze_authority.as_ref(),
+        decimals,
+    )
+    .map_err(|e| ApiError::InstructionFailed(e.to_string()))?;
+    instructions.push(initialize_mint_ix);
+
+    let transaction = match transaction_params {
+        Some(params) => Some(transaction::build(&cluster, &instructions, &params).await?),
+        None => None,
     };
 
-    let mint_to_ix = match spl_token::instruction::mint_to(
-        &TOKEN_PROGRAM_ID,
-        &mint_pubkey,
-        &token_account_pubkey,
-        &mintAuthority_pubkey,
-        &[&mintAuthority_pubkey],
-        payload.amount,
-    ) {
-        Ok(ix) => ix,
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                ResponseJson(ApiResponse::error(format!("Failed to create mint instruction: {}", e)))
-            ));
-        }
+    let response_data = TokenCreateData {
+        cluster: cluster.name.clone(),
+        mint: mint.to_string(),
+        mint_secret,
+        freeze_authority: freeze_authority.map(|freeze_authority| freeze_authority.to_string()),
+        space: space as u64,
+        rent_lamports,
+        instructions: instructions.iter().map(InstructionData::from).collect(),
+        initialize_mint: InstructionData::from(instructions.last().expect("initialize_mint2 was pushed")),
+        transaction,
     };
 
+    Ok(ResponseJson(ApiResponse::success(response_data)))
+}
+
+async fn mint_token(cluster: LazyCluster, ApiJson(payload): ApiJson<TokenMintRequest>) -> ApiResult<TokenMintData> {
+    debug!(?payload, "request payload");
+    let TokenMintParams { mint, mint_authority, token_account, amount, decimals } = payload.validate()?;
+    let transaction_params = payload.transaction.as_ref().map(Validate::validate).transpose()?;
+    let token_program = token_program::resolve(&cluster, payload.token_program, mint).await?.id();
+
+    let mint_to_ix = match decimals {
+        Some(decimals) => mint_to_checked(
+            &token_program,
+            &mint,
+            &token_account,
+            &mint_authority,
+            &[&mint_authority],
+            amount,
+            decimals,
+        ),
+        None => mint_to(
+            &token_program,
+            &mint,
+            &token_account,
+            &mint_authority,
+            &[&mint_authority],
+            amount,
+        ),
+    }
+    .map_err(|e| ApiError::InstructionFailed(e.to_string()))?;
+
     let accounts: Vec<Account> = mint_to_ix.accounts.iter().map(|acc| {
         Account {
             pubkey: acc.pubkey.to_string(),
@@ -566,442 +954,114 @@ async fn mint_token(Json(payload): Json<TokenMintRequest>) -> Result<ResponseJso
         }
     }).collect();
 
-    let instruction_data = base64::encode(&mint_to_ix.data);
+    let instruction_data = BASE64.encode(&mint_to_ix.data);
+
+    let transaction = match transaction_params {
+        Some(params) => Some(transaction::build(&*cluster.get()?, std::slice::from_ref(&mint_to_ix), &params).await?),
+        None => None,
+    };
 
     let response_data = TokenMintData {
-        program_id: TOKEN_PROGRAM_ID.to_string(),
+        cluster: cluster.used_name(),
+        program_id: token_program.to_string(),
         accounts,
         instruction_data,
+        transaction,
     };
 
     Ok(ResponseJson(ApiResponse::success(response_data)))
 }
 
-async fn sign_message(Json(payload): Json<MessageSignRequest>) -> Result<ResponseJson<ApiResponse<MessageSignData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("POST /message/sign payload: {:?}", payload);
-    let message = match &payload.message {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(msg) if msg.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(msg) => msg,
-    };
-
-    let secret = match &payload.secret {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(sec) if sec.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(sec) => sec,
-    };
-
-    let secret_bytes = match bs58::decode(secret).into_vec() {
-        Ok(bytes) => bytes,
-        Err(_) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid secret key format".to_string()))
-            ));
-        }
-    };
-
-    let keypair = match Keypair::from_bytes(&secret_bytes) {
-        Ok(kp) => kp,
-        Err(_) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid secret key".to_string()))
-            ));
-        }
-    };
+async fn sign_message(ApiJson(payload): ApiJson<MessageSignRequest>) -> ApiResult<MessageSignData> {
+    debug!(?payload, "request payload");
+    let MessageSignParams { message, keypair } = payload.validate()?;
 
     let signature = keypair.sign_message(message.as_bytes());
     
-    let signature_base64 = base64::encode(&signature.as_ref());
+    let signature_base64 = BASE64.encode(signature.as_ref());
     
     let public_key = keypair.pubkey().to_string();
 
     let response_data = MessageSignData {
         signature: signature_base64,
         public_key,
-        message: message.clone(),
+        message,
     };
 
     Ok(ResponseJson(ApiResponse::success(response_data)))
 }
 
-async fn verify_message(Json(payload): Json<MessageVerifyRequest>) -> Result<ResponseJson<ApiResponse<MessageVerifyData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("POST /message/verify payload: {:?}", payload);
-    let message = match &payload.message {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(msg) if msg.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(msg) => msg,
-    };
-
-    let signature_str = match &payload.signature {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(sig) if sig.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(sig) => sig,
-    };
-
-    let pubkey_str = match &payload.pubkey {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(pk) if pk.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(pk) => pk,
-    };
-
-    let signature_bytes = match base64::decode(signature_str) {
-        Ok(bytes) => bytes,
-        Err(_) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid signature format".to_string()))
-            ));
-        }
-    };
-
-    let pubkey = match parse_pubkey_flexible(pubkey_str) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid public key: {}", e)))
-            ));
-        }
-    };
-
-    let signature = match Signature::try_from(signature_bytes.as_slice()) {
-        Ok(sig) => sig,
-        Err(_) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid signature".to_string()))
-            ));
-        }
-    };
+async fn verify_message(ApiJson(payload): ApiJson<MessageVerifyRequest>) -> ApiResult<MessageVerifyData> {
+    debug!(?payload, "request payload");
+    let MessageVerifyParams { message, signature, pubkey } = payload.validate()?;
 
     let is_valid = signature.verify(&pubkey.to_bytes(), message.as_bytes());
 
     let response_data = MessageVerifyData {
         valid: is_valid,
-        message: message.clone(),
-        pubkey: pubkey_str.clone(),
+        message,
+        pubkey: payload.pubkey.unwrap_or_default(),
     };
 
     Ok(ResponseJson(ApiResponse::success(response_data)))
 }
 
-async fn send_sol(Json(payload): Json<SendSolRequest>) -> Result<ResponseJson<ApiResponse<SendSolData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("POST /send/sol payload: {:?}", payload);
-    let from_str = match &payload.from {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(addr) if addr.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(addr) => addr,
-    };
-
-    let to_str = match &payload.to {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(addr) if addr.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(addr) => addr,
-    };
-
-    let lamports = match payload.lamports {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required fields".to_string()))
-            ));
-        }
-        Some(amount) if amount == 0 => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid lamports amount: must be greater than 0".to_string()))
-            ));
-        }
-        Some(amount) if amount > u64::MAX / 2 => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid lamports amount: amount too large".to_string()))
-            ));
-        }
-        Some(amount) => amount,
-    };
-
-    let from_pubkey = match parse_pubkey_flexible(from_str) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid 'from' public key: {}", e)))
-            ));
-        }
-    };
-
-    let to_pubkey = match parse_pubkey_flexible(to_str) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid 'to' public key: {}", e)))
-            ));
-        }
-    };
-
-
-    if from_pubkey == to_pubkey {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Cannot send SOL to the same address".to_string()))
-        ));
-    }
-    
-    let zero_pubkey = Pubkey::new_from_array([0u8; 32]);
-    if from_pubkey == zero_pubkey {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Cannot send from zero address".to_string()))
-        ));
-    }
-    
-    if to_pubkey == zero_pubkey {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Cannot send to zero address".to_string()))
-        ));
-    }
-    
-    if to_pubkey == system_program::ID {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Cannot send SOL to system program address".to_string()))
-        ));
-    }
-    
-    const MAX_REASONABLE_LAMPORTS: u64 = 1_000_000_000 * LAMPORTS_PER_SOL; // 1 billion SOL
-    if lamports > MAX_REASONABLE_LAMPORTS {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Lamports amount exceeds reasonable limits".to_string()))
-        ));
-    }
+async fn send_sol(cluster: LazyCluster, ApiJson(payload): ApiJson<SendSolRequest>) -> ApiResult<SendSolData> {
+    debug!(?payload, "request payload");
+    let SendSolParams { from, to, lamports } = payload.validate()?;
+    let transaction_params = payload.transaction.as_ref().map(Validate::validate).transpose()?;
 
-    let transfer_ix: Instruction = system_instruction::transfer(&from_pubkey, &to_pubkey, lamports);
+    let transfer_ix: Instruction = system_instruction::transfer(&from, &to, lamports);
 
     let accounts: Vec<String> = transfer_ix.accounts.iter().map(|acc| {
         acc.pubkey.to_string()
     }).collect();
 
-    let instruction_data = base64::encode(&transfer_ix.data);
+    let instruction_data = BASE64.encode(&transfer_ix.data);
+
+    let transaction = match transaction_params {
+        Some(params) => Some(transaction::build(&*cluster.get()?, std::slice::from_ref(&transfer_ix), &params).await?),
+        None => None,
+    };
 
     let response_data = SendSolData {
+        cluster: cluster.used_name(),
         program_id: system_program::ID.to_string(),
         accounts,
         instruction_data,
+        transaction,
     };
 
     Ok(ResponseJson(ApiResponse::success(response_data)))
 }
 
-async fn send_token(Json(payload): Json<SendTokenRequest>) -> Result<ResponseJson<ApiResponse<SendTokenData>>, (StatusCode, ResponseJson<ApiResponse<()>>)> {
-    println!("POST /send/token payload: {:?}", payload);
-    let destination_str = match &payload.destination {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required field: destination".to_string()))
-            ));
-        }
-        Some(addr) if addr.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid destination: cannot be empty".to_string()))
-            ));
-        }
-        Some(addr) => addr,
-    };
-
-    let mint_str = match &payload.mint {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required field: mint".to_string()))
-            ));
-        }
-        Some(addr) if addr.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid mint: cannot be empty".to_string()))
-            ));
-        }
-        Some(addr) => addr,
-    };
-
-    let owner_str = match &payload.owner {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required field: owner".to_string()))
-            ));
-        }
-        Some(addr) if addr.is_empty() => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid owner: cannot be empty".to_string()))
-            ));
-        }
-        Some(addr) => addr,
-    };
-
-    let amount = match payload.amount {
-        None => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Missing required field: amount".to_string()))
-            ));
-        }
-        Some(amt) if amt == 0 => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid amount: must be greater than 0".to_string()))
-            ));
-        }
-        Some(amt) if amt > u64::MAX / 2 => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error("Invalid amount: amount too large".to_string()))
-            ));
-        }
-        Some(amt) => amt,
-    };
-
-    // Use provided decimals or default to 9 (most common for SPL tokens)
-    let decimals = payload.decimals.unwrap_or(9);
-
-    // Parse public keys using flexible format support
-    let destination_pubkey = match parse_pubkey_flexible(destination_str) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid 'destination' public key: {}", e)))
-            ));
-        }
-    };
-
-    let mint_pubkey = match parse_pubkey_flexible(mint_str) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid 'mint' public key: {}", e)))
-            ));
-        }
-    };
-
-    let owner_pubkey = match parse_pubkey_flexible(owner_str) {
-        Ok(pk) => pk,
-        Err(e) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                ResponseJson(ApiResponse::error(format!("Invalid 'owner' public key: {}", e)))
-            ));
-        }
-    };
+async fn send_token(cluster: LazyCluster, ApiJson(payload): ApiJson<SendTokenRequest>) -> ApiResult<SendTokenData> {
+    debug!(?payload, "request payload");
+    let SendTokenParams { destination, mint, owner, amount, decimals } = payload.validate()?;
+    let transaction_params = payload.transaction.as_ref().map(Validate::validate).transpose()?;
+    let token_program = token_program::resolve(&cluster, payload.token_program, mint).await?.id();
 
-    let source_token_account = get_associated_token_address(&owner_pubkey, &mint_pubkey);
-    let destination_token_account = get_associated_token_address(&destination_pubkey, &mint_pubkey);
+    let source_token_account = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+    let destination_token_account = get_associated_token_address_with_program_id(&destination, &mint, &token_program);
 
     if source_token_account == destination_token_account {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            ResponseJson(ApiResponse::error("Cannot transfer tokens to the same account".to_string()))
-        ));
+        return Err(ApiError::InvalidAccount {
+            field: "destination",
+            reason: "Cannot transfer tokens to the same account".to_string(),
+        });
     }
 
-    let transfer_ix = match transfer_checked(
-        &TOKEN_PROGRAM_ID,
+    let transfer_ix = transfer_checked(
+        &token_program,
         &source_token_account,
-        &mint_pubkey,
+        &mint,
         &destination_token_account,
-        &owner_pubkey,
-        &[&owner_pubkey],
+        &owner,
+        &[&owner],
         amount,
         decimals,
-    ) {
-        Ok(ix) => ix,
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                ResponseJson(ApiResponse::error(format!("Failed to create transfer instruction: {}", e)))
-            ));
-        }
-    };
+    )
+    .map_err(|e| ApiError::InstructionFailed(e.to_string()))?;
 
     let accounts: Vec<TokenAccount> = transfer_ix.accounts.iter().map(|acc| {
         TokenAccount {
@@ -1010,14 +1070,179 @@ async fn send_token(Json(payload): Json<SendTokenRequest>) -> Result<ResponseJso
         }
     }).collect();
 
-    let instruction_data = base64::encode(&transfer_ix.data);
+    let instruction_data = BASE64.encode(&transfer_ix.data);
+
+    let transaction = match transaction_params {
+        Some(params) => Some(transaction::build(&*cluster.get()?, std::slice::from_ref(&transfer_ix), &params).await?),
+        None => None,
+    };
 
     let response_data = SendTokenData {
-        program_id: TOKEN_PROGRAM_ID.to_string(),
+        cluster: cluster.used_name(),
+        program_id: token_program.to_string(),
         accounts,
         instruction_data,
+        transaction,
     };
 
     Ok(ResponseJson(ApiResponse::success(response_data)))
 }
 
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+
+    /// Collects everything a `fmt` subscriber writes.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl CapturedLogs {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn sign_message_never_logs_the_secret() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let keypair = Keypair::new();
+        let secret = bs58::encode(keypair.to_bytes()).into_string();
+        let payload = MessageSignRequest {
+            message: Some("Hello, Solana!".to_string()),
+            secret: Some(Secret::new(secret.clone())),
+        };
+        assert!(sign_message(ApiJson(payload)).await.is_ok());
+
+        let logs = logs.contents();
+        assert!(logs.contains("[REDACTED]"), "payload was not logged: {logs}");
+        assert!(!logs.contains(&secret));
+    }
+
+    const MINT_AUTHORITY: &str = "B2UY29wPcL71jigJiM4hp45tEnhXJRnimYAZEWy8JV6J";
+    const FREEZE_AUTHORITY: &str = "3CrkqM8pPJafybHjKnGpc3eq4i3sHSj8331sWKbfiM1g";
+
+    fn validate_create(body: serde_json::Value) -> Result<TokenCreateParams, ApiError> {
+        serde_json::from_value::<TokenCreateRequest>(body).unwrap().validate()
+    }
+
+    fn error_fields(err: ApiError) -> Vec<(&'static str, Option<String>)> {
+        err.body().errors.into_iter().map(|e| (e.code, e.field)).collect()
+    }
+
+    #[test]
+    fn freeze_authority_defaults_to_mint_authority() {
+        let params = validate_create(serde_json::json!({
+            "mintAuthority": MINT_AUTHORITY,
+            "decimals": 6,
+        }))
+        .unwrap();
+        assert_eq!(params.freeze_authority, Some(MINT_AUTHORITY.parse().unwrap()));
+    }
+
+    #[test]
+    fn null_freeze_authority_means_none() {
+        let params = validate_create(serde_json::json!({
+            "mintAuthority": MINT_AUTHORITY,
+            "freezeAuthority": null,
+            "decimals": 6,
+        }))
+        .unwrap();
+        assert_eq!(params.freeze_authority, None);
+    }
+
+    #[test]
+    fn explicit_freeze_authority_is_used() {
+        let params = validate_create(serde_json::json!({
+            "mintAuthority": MINT_AUTHORITY,
+            "freezeAuthority": FREEZE_AUTHORITY,
+            "decimals": 6,
+        }))
+        .unwrap();
+        assert_eq!(params.freeze_authority, Some(FREEZE_AUTHORITY.parse().unwrap()));
+        assert_eq!(params.mint_authority, MINT_AUTHORITY.parse().unwrap());
+    }
+
+    #[test]
+    fn invalid_freeze_authority_is_reported() {
+        let err = validate_create(serde_json::json!({
+            "mintAuthority": MINT_AUTHORITY,
+            "freezeAuthority": "not-a-key",
+            "decimals": 6,
+        }))
+        .err()
+        .unwrap();
+        assert_eq!(error_fields(err), [("INVALID_PUBKEY", Some("freezeAuthority".to_string()))]);
+    }
+
+    #[test]
+    fn frozen_default_state_needs_a_freeze_authority() {
+        let err = validate_create(serde_json::json!({
+            "mintAuthority": MINT_AUTHORITY,
+            "freezeAuthority": null,
+            "decimals": 6,
+            "tokenProgram": "token-2022",
+            "extensions": { "defaultAccountState": "frozen" },
+        }))
+        .err()
+        .unwrap();
+        assert_eq!(
+            error_fields(err),
+            [("INVALID_EXTENSION", Some("extensions.defaultAccountState".to_string()))]
+        );
+    }
+
+    fn validate_mint(amount: u64, decimals: Option<u8>) -> Result<TokenMintParams, ApiError> {
+        serde_json::from_value::<TokenMintRequest>(serde_json::json!({
+            "mint": FREEZE_AUTHORITY,
+            "mintAuthority": MINT_AUTHORITY,
+            "tokenAccount": MINT_AUTHORITY,
+            "amount": amount,
+            "decimals": decimals,
+        }))
+        .unwrap()
+        .validate()
+    }
+
+    #[test]
+    fn mint_accepts_any_non_zero_amount() {
+        assert_eq!(validate_mint(u64::MAX, None).unwrap().amount, u64::MAX);
+        assert_eq!(
+            error_fields(validate_mint(0, None).err().unwrap()),
+            [("INVALID_AMOUNT", Some("amount".to_string()))]
+        );
+    }
+
+    #[test]
+    fn mint_decimals_are_checked_when_given() {
+        assert_eq!(validate_mint(1, None).unwrap().decimals, None);
+        assert_eq!(validate_mint(1, Some(6)).unwrap().decimals, Some(6));
+        assert_eq!(
+            error_fields(validate_mint(1, Some(10)).err().unwrap()),
+            [("INVALID_DECIMALS", Some("decimals".to_string()))]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+use crate::state::AppState;
+
+/// Error code of a failed request, left in the response extensions by
+/// `ApiError::into_response` so the metrics middleware can count it.
+#[derive(Clone, Copy)]
+pub struct ErrorCode(pub &'static str);
+
+/// All Prometheus collectors exported on `/metrics`.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    http_errors: IntCounterVec,
+    rpc_duration: HistogramVec,
+    rpc_failures: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled, by route and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency, by route"),
+            &["method", "route"],
+        )
+        .unwrap();
+        let http_errors = IntCounterVec::new(
+            Opts::new("http_errors_total", "Failed HTTP requests, by route and API error code"),
+            &["route", "code"],
+        )
+        .unwrap();
+        let rpc_duration = HistogramVec::new(
+            HistogramOpts::new("rpc_call_duration_seconds", "Solana RPC call latency, by cluster and method"),
+            &["cluster", "method"],
+        )
+        .unwrap();
+        let rpc_failures = IntCounterVec::new(
+            Opts::new("rpc_call_failures_total", "Failed Solana RPC calls, by cluster and method"),
+            &["cluster", "method"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry.register(Box::new(http_duration.clone())).unwrap();
+        registry.register(Box::new(http_errors.clone())).unwrap();
+        registry.register(Box::new(rpc_duration.clone())).unwrap();
+        registry.register(Box::new(rpc_failures.clone())).unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_duration,
+            http_errors,
+            rpc_duration,
+            rpc_failures,
+        }
+    }
+
+    /// Time `call` and record it as one RPC call made against `cluster`.
+    pub async fn time_rpc<T, E>(
+        &self,
+        cluster: &str,
+        method: &str,
+        call: impl std::future::Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let start = Instant::now();
+        let result = call.await;
+        self.observe_rpc(cluster, method, start.elapsed(), result.is_ok());
+        result
+    }
+
+    /// Record one RPC call made against `cluster`.
+    pub fn observe_rpc(&self, cluster: &str, method: &str, elapsed: Duration, ok: bool) {
+        self.rpc_duration
+            .with_label_values(&[cluster, method])
+            .observe(elapsed.as_secs_f64());
+        if !ok {
+            self.rpc_failures.with_label_values(&[cluster, method]).inc();
+        }
+    }
+
+    fn render(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer).unwrap_or_default())
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Middleware recording request count, latency and error codes. Labels use
+/// the matched route template rather than the raw path to keep cardinality
+/// bounded.
+pub async fn track_requests(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = start.elapsed();
+
+    let metrics = &state.metrics;
+    let status = response.status().as_u16().to_string();
+    metrics
+        .http_requests
+        .with_label_values(&[&method, &route, &status])
+        .inc();
+    metrics
+        .http_duration
+        .with_label_values(&[&method, &route])
+        .observe(elapsed.as_secs_f64());
+    if let Some(ErrorCode(code)) = response.extensions().get::<ErrorCode>() {
+        metrics.http_errors.with_label_values(&[&route, code]).inc();
+    }
+
+    response
+}
+
+pub async fn metrics_handler(State(state): State<AppState>) -> Response {
+    match state.metrics.render() {
+        Ok(body) => ([(header::CONTENT_TYPE, TextEncoder::new().format_type().to_string())], body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
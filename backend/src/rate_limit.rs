@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ipnet::IpNet;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::auth::AuthenticatedKey;
+use crate::config::{BucketConfig, RateLimitConfig};
+use crate::error::ApiError;
+use crate::state::AppState;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+const RATELIMIT_POLICY: HeaderName = HeaderName::from_static("ratelimit-policy");
+
+/// Parse a `trusted_proxies` entry: a single address or a CIDR range.
+pub fn parse_proxy(entry: &str) -> Result<IpNet, String> {
+    entry
+        .parse::<IpNet>()
+        .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("'{}' is not an IP address or CIDR range", entry))
+}
+
+/// Who a request is charged to.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+enum Client {
+    Ip(IpAddr),
+    /// Id of the API key, or public key of the signer, the request
+    /// authenticated with.
+    ApiKey(String),
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Outcome of taking a token, with the numbers reported in `RateLimit-*`.
+struct Decision {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    /// Seconds until the bucket is full again.
+    reset_secs: u64,
+    /// Seconds until the next token, when denied.
+    retry_after_secs: u64,
+    period_secs: u64,
+}
+
+/// Token buckets per route and client.
+///
+/// Every route has its own bucket per client, sized by the route's entry in
+/// `rate_limit.routes` or by `rate_limit.default`. A bucket holds `requests`
+/// tokens and refills evenly over `period_secs`.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    trusted_proxies: Vec<IpNet>,
+    buckets: Mutex<HashMap<(String, Client), Bucket>>,
+}
+
+impl RateLimiter {
+    /// `config` was validated by `Config::load`.
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            config: config.clone(),
+            trusted_proxies: config
+                .trusted_proxies
+                .iter()
+                .map(|entry| parse_proxy(entry).expect("validated proxy entry"))
+                .collect(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+
+    /// The address the request came from. `X-Forwarded-For` is only believed
+    /// when the peer is a trusted proxy, and then read right to left up to the
+    /// first hop that is not itself trusted.
+    fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+        let hops: Vec<IpAddr> = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|hop| hop.trim().parse().ok())
+            .collect();
+        hops.into_iter().rev().find(|ip| !self.is_trusted(*ip)).unwrap_or(peer)
+    }
+
+    fn client(&self, peer: IpAddr, request: &Request) -> Client {
+        match request.extensions().get::<AuthenticatedKey>() {
+            Some(key) => Client::ApiKey(key.id.clone()),
+            None => Client::Ip(self.client_ip(peer, request.headers())),
+        }
+    }
+
+    fn bucket_config(&self, route: &str) -> &BucketConfig {
+        self.config.routes.get(route).unwrap_or(&self.config.default)
+    }
+
+    fn take(&self, route: &str, client: Client) -> Decision {
+        let config = self.bucket_config(route);
+        let capacity = f64::from(config.requests);
+        let rate = capacity / config.period_secs as f64;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry((route.to_string(), client)).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(capacity);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        Decision {
+            allowed,
+            limit: config.requests,
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs: ((capacity - bucket.tokens) / rate).ceil() as u64,
+            retry_after_secs: ((1.0 - bucket.tokens).max(0.0) / rate).ceil() as u64,
+            period_secs: config.period_secs,
+        }
+    }
+
+    /// Give back the token taken by [`RateLimiter::take`] for a request whose
+    /// response means it did no work, if the route's bucket asks for that.
+    /// Updates the numbers `decision` reports.
+    fn refund(&self, route: &str, client: Client, status: StatusCode, decision: &mut Decision) {
+        let config = self.bucket_config(route);
+        let failed = status == StatusCode::SERVICE_UNAVAILABLE
+            || (status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS);
+        if !config.refund_failures || !failed {
+            return;
+        }
+        let capacity = f64::from(config.requests);
+        let rate = capacity / config.period_secs as f64;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if let Some(bucket) = buckets.get_mut(&(route.to_string(), client)) {
+            bucket.tokens = (bucket.tokens + 1.0).min(capacity);
+            decision.remaining = bucket.tokens.floor() as u32;
+            decision.reset_secs = ((capacity - bucket.tokens) / rate).ceil() as u64;
+        }
+    }
+
+    /// Drop buckets that have refilled completely; they are indistinguishable
+    /// from a fresh one.
+    fn sweep(&self) {
+        let now = Instant::now();
+        self.buckets.lock().unwrap().retain(|(route, _), bucket| {
+            let config = self.bucket_config(route);
+            let full_after = Duration::from_secs(config.period_secs);
+            now.duration_since(bucket.updated) < full_after
+        });
+    }
+
+    /// Run [`RateLimiter::sweep`] every `interval` until `shutdown` is
+    /// cancelled.
+    pub fn spawn_sweeper(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => self.sweep(),
+                    _ = shutdown.cancelled() => break,
+                }
+            }
+        })
+    }
+}
+
+/// Route middleware charging each request to its client's bucket for the
+/// route: the API key's when [`crate::auth::authenticate`] ran first and
+/// found one, the IP's otherwise. Meant for `route_layer` on the API router,
+/// where the URI no longer carries the API prefix, so routes are named as in
+/// `rate_limit.routes`.
+pub async fn limit_requests(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limiter = &state.rate_limiter;
+    let route = request.uri().path().to_string();
+    let client = limiter.client(peer.ip(), &request);
+    let mut decision = limiter.take(&route, client.clone());
+
+    let mut response = if decision.allowed {
+        let response = next.run(request).await;
+        limiter.refund(&route, client, response.status(), &mut decision);
+        response
+    } else {
+        tracing::warn!(?client, %route, "rate limit exceeded");
+        let mut response = ApiError::RateLimited {
+            retry_after_secs: decision.retry_after_secs,
+        }
+        .into_response();
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(decision.retry_after_secs));
+        response
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(RATELIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(RATELIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(RATELIMIT_RESET, HeaderValue::from(decision.reset_secs));
+    if let Ok(policy) = HeaderValue::from_str(&format!("{};w={}", decision.limit, decision.period_secs)) {
+        headers.insert(RATELIMIT_POLICY, policy);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn limiter(refund_failures: bool) -> RateLimiter {
+        RateLimiter::new(&RateLimitConfig {
+            enabled: true,
+            trusted_proxies: Vec::new(),
+            default: BucketConfig { requests: 120, period_secs: 60, refund_failures: false },
+            routes: BTreeMap::from([(
+                "/airdrop".to_string(),
+                BucketConfig { requests: 2, period_secs: 3600, refund_failures },
+            )]),
+        })
+    }
+
+    fn client() -> Client {
+        Client::Ip(IpAddr::from([203, 0, 113, 7]))
+    }
+
+    #[test]
+    fn failed_airdrops_are_refunded() {
+        let limiter = limiter(true);
+        for status in [StatusCode::SERVICE_UNAVAILABLE, StatusCode::FORBIDDEN, StatusCode::SERVICE_UNAVAILABLE] {
+            let mut decision = limiter.take("/airdrop", client());
+            assert!(decision.allowed);
+            limiter.refund("/airdrop", client(), status, &mut decision);
+            assert_eq!(decision.remaining, 2);
+        }
+    }
+
+    #[test]
+    fn served_airdrops_are_charged() {
+        let limiter = limiter(true);
+        for status in [StatusCode::OK, StatusCode::BAD_GATEWAY] {
+            let mut decision = limiter.take("/airdrop", client());
+            assert!(decision.allowed);
+            limiter.refund("/airdrop", client(), status, &mut decision);
+        }
+        assert!(!limiter.take("/airdrop", client()).allowed);
+    }
+
+    #[test]
+    fn failures_are_charged_unless_configured() {
+        let limiter = limiter(false);
+        for _ in 0..2 {
+            let mut decision = limiter.take("/airdrop", client());
+            limiter.refund("/airdrop", client(), StatusCode::SERVICE_UNAVAILABLE, &mut decision);
+        }
+        assert!(!limiter.take("/airdrop", client()).allowed);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_custom_error::JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+    rpc_request::RpcError,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::metrics::Metrics;
+
+/// Whether `err` means we never got a usable answer from the node, as
+/// opposed to the node rejecting the request itself. `RpcRequestError` is
+/// how the client reports failures in its own preflight queries (e.g. the
+/// node version check).
+pub fn is_transport_error(err: &ClientError) -> bool {
+    matches!(
+        err.kind(),
+        ClientErrorKind::Io(_)
+            | ClientErrorKind::Reqwest(_)
+            | ClientErrorKind::RpcError(RpcError::RpcRequestError(_))
+    )
+}
+
+/// Errors worth retrying on another node: transport failures and nodes that
+/// report themselves as behind.
+fn is_retryable(err: &ClientError) -> bool {
+    is_transport_error(err)
+        || matches!(
+            err.kind(),
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+                if *code == JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+        )
+}
+
+struct RpcNode {
+    url: String,
+    client: Arc<RpcClient>,
+    healthy: AtomicBool,
+    slot: AtomicU64,
+}
+
+/// Several RPC endpoints for one cluster.
+///
+/// Nodes start out healthy. [`RpcPool::check_health`] (run periodically by
+/// [`RpcPool::spawn_health_checks`]) marks nodes that fail `getHealth` /
+/// `getSlot`, or whose slot trails the best node by more than
+/// `max_slot_lag`, as unhealthy. Requests go to healthy nodes first, in
+/// configured order; unhealthy nodes are only tried when nothing else is
+/// left.
+pub struct RpcPool {
+    cluster: String,
+    nodes: Vec<RpcNode>,
+    max_slot_lag: u64,
+    metrics: Arc<Metrics>,
+    last_check: Mutex<Option<Instant>>,
+}
+
+/// Point-in-time view of one node, as of the last health check.
+#[derive(Serialize)]
+pub struct NodeStatus {
+    pub url: String,
+    pub healthy: bool,
+    pub slot: u64,
+    /// Slots behind the best node in the pool.
+    pub slot_lag: u64,
+}
+
+impl RpcPool {
+    /// `urls` must not be empty. Every call is recorded in `metrics` under
+    /// the `cluster` label.
+    pub fn new(
+        cluster: &str,
+        urls: &[String],
+        timeout: Duration,
+        commitment: CommitmentConfig,
+        max_slot_lag: u64,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        assert!(!urls.is_empty(), "an RPC pool needs at least one URL");
+        let nodes = urls
+            .iter()
+            .map(|url| RpcNode {
+                url: url.clone(),
+                client: Arc::new(RpcClient::new_with_timeout_and_commitment(url.clone(), timeout, commitment)),
+                healthy: AtomicBool::new(true),
+                slot: AtomicU64::new(0),
+            })
+            .collect();
+        Self {
+            cluster: cluster.to_string(),
+            nodes,
+            max_slot_lag,
+            metrics,
+            last_check: Mutex::new(None),
+        }
+    }
+
+    /// Probe every node and update its health.
+    pub async fn check_health(&self) {
+        let probes = self.nodes.iter().map(|node| async move {
+            self.metrics
+                .time_rpc(&self.cluster, "getHealth", node.client.get_health())
+                .await
+                .ok()?;
+            self.metrics
+                .time_rpc(&self.cluster, "getSlot", node.client.get_slot())
+                .await
+                .ok()
+        });
+        let slots = futures::future::join_all(probes).await;
+        let best = slots.iter().flatten().copied().max();
+
+        for (node, slot) in self.nodes.iter().zip(slots) {
+            let healthy = match (slot, best) {
+                (Some(slot), Some(best)) => {
+                    node.slot.store(slot, Ordering::Relaxed);
+                    best - slot <= self.max_slot_lag
+                }
+                _ => false,
+            };
+            if slot.is_none() {
+                node.slot.store(0, Ordering::Relaxed);
+            }
+            if node.healthy.swap(healthy, Ordering::Relaxed) != healthy {
+                if healthy {
+                    tracing::info!(url = %node.url, ?slot, "RPC node back in rotation");
+                } else {
+                    tracing::warn!(url = %node.url, ?slot, ?best, "RPC node taken out of rotation");
+                }
+            }
+        }
+        *self.last_check.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// When [`RpcPool::check_health`] last completed, if ever.
+    pub fn last_check(&self) -> Option<Instant> {
+        *self.last_check.lock().unwrap()
+    }
+
+    pub fn status(&self) -> Vec<NodeStatus> {
+        let best = self.nodes.iter().map(|n| n.slot.load(Ordering::Relaxed)).max().unwrap_or(0);
+        self.nodes
+            .iter()
+            .map(|node| {
+                let slot = node.slot.load(Ordering::Relaxed);
+                NodeStatus {
+                    url: node.url.clone(),
+                    healthy: node.healthy.load(Ordering::Relaxed),
+                    slot,
+                    slot_lag: best - slot,
+                }
+            })
+            .collect()
+    }
+
+    /// Run [`RpcPool::check_health`] every `interval` until `shutdown` is
+    /// cancelled.
+    pub fn spawn_health_checks(self: Arc<Self>, interval: Duration, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => self.check_health().await,
+                    _ = shutdown.cancelled() => break,
+                }
+            }
+        })
+    }
+
+    /// Healthy nodes first, then the rest, each group in configured order.
+    fn candidates(&self) -> impl Iterator<Item = &RpcNode> {
+        let healthy = self.nodes.iter().filter(|n| n.healthy.load(Ordering::Relaxed));
+        let unhealthy = self.nodes.iter().filter(|n| !n.healthy.load(Ordering::Relaxed));
+        healthy.chain(unhealthy)
+    }
+
+    /// Run an idempotent read, moving on to the next node when one fails in
+    /// a way another node could fix. `method` is the JSON-RPC method name,
+    /// used as a metrics label.
+    pub async fn read<T, F, Fut>(&self, method: &str, op: F) -> ClientResult<T>
+    where
+        F: Fn(Arc<RpcClient>) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        let mut last_err = None;
+        for node in self.candidates() {
+            match self.metrics.time_rpc(&self.cluster, method, op(node.client.clone())).await {
+                Ok(value) => return Ok(value),
+                Err(err) if is_retryable(&err) => {
+                    tracing::warn!(url = %node.url, error = %err, "RPC read failed, trying next node");
+                    node.healthy.store(false, Ordering::Relaxed);
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(last_err.expect("pool has at least one node"))
+    }
+
+    /// Run a request that must not be repeated (e.g. an airdrop) against a
+    /// single node.
+    pub async fn send<T, F, Fut>(&self, method: &str, op: F) -> ClientResult<T>
+    where
+        F: FnOnce(Arc<RpcClient>) -> Fut,
+        Fut: Future<Output = ClientResult<T>>,
+    {
+        let node = self.candidates().next().expect("pool has at least one node");
+        let result = self.metrics.time_rpc(&self.cluster, method, op(node.client.clone())).await;
+        if matches!(&result, Err(err) if is_transport_error(err)) {
+            node.healthy.store(false, Ordering::Relaxed);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// A local JSON-RPC node that answers at `slot` and records every method
+    /// it is asked for, apart from the client's own `getVersion` preflight.
+    /// An unhealthy node answers everything else with the "node is behind"
+    /// error.
+    async fn mock_node(slot: u64, unhealthy: bool) -> (String, Arc<Mutex<Vec<String>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<Value>| {
+                let recorded = recorded.clone();
+                async move {
+                    let method = request["method"].as_str().unwrap_or_default().to_string();
+                    let id = request["id"].clone();
+                    if method == "getVersion" {
+                        let version = json!({ "solana-core": "1.18.26", "feature-set": 0 });
+                        return Json(json!({ "jsonrpc": "2.0", "id": id, "result": version }));
+                    }
+                    recorded.lock().unwrap().push(method.clone());
+                    if unhealthy {
+                        return Json(json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "code": JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY, "message": "Node is behind" },
+                        }));
+                    }
+                    let result = match method.as_str() {
+                        "getHealth" => json!("ok"),
+                        "getSlot" => json!(slot),
+                        "getBalance" => json!({ "context": { "slot": slot }, "value": 42 }),
+                        other => panic!("mock node got unexpected method {other}"),
+                    };
+                    Json(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, calls)
+    }
+
+    /// A URL nothing is listening on, so connections are refused.
+    async fn refused_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    fn pool(urls: &[String], max_slot_lag: u64) -> RpcPool {
+        RpcPool::new(
+            "test",
+            urls,
+            Duration::from_secs(5),
+            CommitmentConfig::confirmed(),
+            max_slot_lag,
+            Arc::new(Metrics::new()),
+        )
+    }
+
+    async fn balance(pool: &RpcPool) -> ClientResult<u64> {
+        let pubkey = solana_sdk::pubkey::Pubkey::new_unique();
+        pool.read("getBalance", |rpc| async move { rpc.get_balance(&pubkey).await }).await
+    }
+
+    #[tokio::test]
+    async fn read_fails_over_to_next_node() {
+        let (url, calls) = mock_node(100, false).await;
+        let pool = pool(&[refused_url().await, url], 50);
+
+        assert_eq!(balance(&pool).await.unwrap(), 42);
+        assert_eq!(*calls.lock().unwrap(), ["getBalance"]);
+
+        let status = pool.status();
+        assert!(!status[0].healthy);
+        assert!(status[1].healthy);
+    }
+
+    #[tokio::test]
+    async fn lagging_node_leaves_rotation() {
+        let (lagging_url, lagging_calls) = mock_node(900, false).await;
+        let (url, calls) = mock_node(1000, false).await;
+        let pool = pool(&[lagging_url, url], 50);
+
+        pool.check_health().await;
+        let status = pool.status();
+        assert!(!status[0].healthy);
+        assert_eq!(status[0].slot_lag, 100);
+        assert!(status[1].healthy);
+
+        lagging_calls.lock().unwrap().clear();
+        calls.lock().unwrap().clear();
+        assert_eq!(balance(&pool).await.unwrap(), 42);
+        assert!(lagging_calls.lock().unwrap().is_empty());
+        assert_eq!(*calls.lock().unwrap(), ["getBalance"]);
+    }
+
+    #[tokio::test]
+    async fn send_is_never_retried() {
+        let (unhealthy_url, unhealthy_calls) = mock_node(100, true).await;
+        let (url, calls) = mock_node(100, false).await;
+        let pool = pool(&[unhealthy_url, url], 50);
+
+        let pubkey = solana_sdk::pubkey::Pubkey::new_unique();
+        let result = pool
+            .send("requestAirdrop", |rpc| async move { rpc.request_airdrop(&pubkey, 1).await })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(*unhealthy_calls.lock().unwrap(), ["requestAirdrop"]);
+        assert!(calls.lock().unwrap().is_empty());
+    }
+}
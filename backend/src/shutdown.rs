@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+
+/// Resolve on the first SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("failed to install Ctrl-C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Cancel `token` when a shutdown signal arrives. Everything that should
+/// stop on shutdown (servers, background tasks, readiness) watches the same
+/// token.
+pub fn cancel_on_signal(token: CancellationToken) {
+    tokio::spawn(async move {
+        signal().await;
+        tracing::info!("shutdown signal received, draining");
+        token.cancel();
+    });
+}
+
+/// Future handed to `with_graceful_shutdown`. Once `token` is cancelled it
+/// waits `readiness_delay` before letting the server stop accepting
+/// connections, giving load balancers time to see `/readyz` fail.
+pub async fn stop_accepting(token: CancellationToken, readiness_delay: Duration) {
+    token.cancelled().await;
+    tokio::time::sleep(readiness_delay).await;
+}
+
+/// Resolves once in-flight requests have had `drain_timeout` to finish
+/// after the server stopped accepting. Racing the server against this bounds
+/// how long a redeploy can take.
+pub async fn drain_deadline(token: CancellationToken, readiness_delay: Duration, drain_timeout: Duration) {
+    token.cancelled().await;
+    tokio::time::sleep(readiness_delay + drain_timeout).await;
+}
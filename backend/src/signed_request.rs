@@ -0,0 +1,218 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::http::{HeaderMap, Method};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use sha2::{Digest, Sha256};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use crate::auth::Role;
+use crate::config::AuthConfig;
+
+/// Base58 public key of the signing keypair.
+pub const PUBKEY_HEADER: &str = "x-solana-pubkey";
+/// Base64 ed25519 signature of [`canonical_request`].
+pub const SIGNATURE_HEADER: &str = "x-solana-signature";
+/// Unix seconds at which the request was signed.
+pub const TIMESTAMP_HEADER: &str = "x-solana-timestamp";
+
+/// The string a client signs:
+///
+/// ```text
+/// POST
+/// /api/send/sol?cluster=devnet
+/// <hex SHA-256 of the body>
+/// 1700000000
+/// ```
+///
+/// `path` is the path and query exactly as sent, API prefix included. An
+/// empty body hashes to the SHA-256 of no bytes.
+pub fn canonical_request(method: &Method, path: &str, body: &[u8], timestamp: u64) -> String {
+    format!("{}\n{}\n{}\n{}", method, path, hex::encode(Sha256::digest(body)), timestamp)
+}
+
+/// Whether the request carries any of the signature headers, and so should be
+/// authenticated as a signed request rather than by API key.
+pub fn is_signed(headers: &HeaderMap) -> bool {
+    [PUBKEY_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER]
+        .iter()
+        .any(|name| headers.contains_key(*name))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Keypairs registered in `auth.signers`, and the signatures already
+/// accepted from them.
+pub struct RequestSigners {
+    roles: HashMap<Pubkey, Role>,
+    max_skew_secs: u64,
+    /// Accepted signatures to the timestamp they were made at. Kept until the
+    /// timestamp falls outside the skew window, after which the request would
+    /// be refused as stale anyway.
+    seen: Mutex<HashMap<Signature, u64>>,
+}
+
+impl RequestSigners {
+    /// `config` was validated by `Config::load`.
+    pub fn new(config: &AuthConfig) -> Self {
+        Self {
+            roles: config
+                .signers
+                .iter()
+                .map(|signer| (signer.pubkey.parse().expect("validated signer pubkey"), signer.role))
+                .collect(),
+            max_skew_secs: config.max_clock_skew_secs,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.roles.len()
+    }
+
+    /// Check the signature headers against the request and return the
+    /// signer and its role. A signature is accepted once.
+    pub fn verify(
+        &self,
+        method: &Method,
+        path: &str,
+        body: &[u8],
+        headers: &HeaderMap,
+    ) -> Result<(Pubkey, Role), String> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .ok_or_else(|| format!("signed requests need the {} header", name))
+        };
+        let pubkey = header(PUBKEY_HEADER)?
+            .parse::<Pubkey>()
+            .map_err(|_| format!("{} is not a valid public key", PUBKEY_HEADER))?;
+        let signature = BASE64
+            .decode(header(SIGNATURE_HEADER)?)
+            .ok()
+            .and_then(|bytes| Signature::try_from(bytes.as_slice()).ok())
+            .ok_or_else(|| format!("{} must be a base64 encoded 64-byte signature", SIGNATURE_HEADER))?;
+        let timestamp = header(TIMESTAMP_HEADER)?
+            .parse::<u64>()
+            .map_err(|_| format!("{} must be unix seconds", TIMESTAMP_HEADER))?;
+
+        let role = *self.roles.get(&pubkey).ok_or("public key is not a registered signer")?;
+        let now = now();
+        if now.abs_diff(timestamp) > self.max_skew_secs {
+            return Err(format!("timestamp is more than {}s from the server clock", self.max_skew_secs));
+        }
+        let message = canonical_request(method, path, body, timestamp);
+        if !signature.verify(&pubkey.to_bytes(), message.as_bytes()) {
+            return Err("signature does not match the request".to_string());
+        }
+
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, signed_at| now.abs_diff(*signed_at) <= self.max_skew_secs);
+        if seen.insert(signature, timestamp).is_some() {
+            return Err("request was already used".to_string());
+        }
+        Ok((pubkey, role))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signer::{keypair::Keypair, Signer};
+
+    use super::*;
+    use crate::config::RequestSignerConfig;
+
+    const PATH: &str = "/api/send/sol?cluster=devnet";
+    const BODY: &[u8] = br#"{"from":"a","to":"b","lamports":1}"#;
+
+    fn signers(keypair: &Keypair) -> RequestSigners {
+        RequestSigners::new(&AuthConfig {
+            signers: vec![RequestSignerConfig { pubkey: keypair.pubkey().to_string(), role: Role::Builder }],
+            max_clock_skew_secs: 300,
+            ..AuthConfig::default()
+        })
+    }
+
+    fn signed_headers(keypair: &Keypair, method: &Method, path: &str, body: &[u8], timestamp: u64) -> HeaderMap {
+        let signature = keypair.sign_message(canonical_request(method, path, body, timestamp).as_bytes());
+        let mut headers = HeaderMap::new();
+        headers.insert(PUBKEY_HEADER, keypair.pubkey().to_string().parse().unwrap());
+        headers.insert(SIGNATURE_HEADER, BASE64.encode(signature.as_ref()).parse().unwrap());
+        headers.insert(TIMESTAMP_HEADER, timestamp.into());
+        headers
+    }
+
+    #[test]
+    fn canonical_request_covers_method_path_body_and_timestamp() {
+        assert_eq!(
+            canonical_request(&Method::POST, PATH, b"", 1_700_000_000),
+            "POST\n/api/send/sol?cluster=devnet\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n1700000000"
+        );
+    }
+
+    #[test]
+    fn accepts_valid_signature() {
+        let keypair = Keypair::new();
+        let headers = signed_headers(&keypair, &Method::POST, PATH, BODY, now());
+
+        let (pubkey, role) = signers(&keypair).verify(&Method::POST, PATH, BODY, &headers).unwrap();
+        assert_eq!(pubkey, keypair.pubkey());
+        assert_eq!(role, Role::Builder);
+    }
+
+    #[test]
+    fn rejects_timestamp_outside_skew() {
+        let keypair = Keypair::new();
+        let signers = signers(&keypair);
+        for timestamp in [now() - 301, now() + 301] {
+            let headers = signed_headers(&keypair, &Method::POST, PATH, BODY, timestamp);
+            let err = signers.verify(&Method::POST, PATH, BODY, &headers).unwrap_err();
+            assert!(err.contains("timestamp"), "{err}");
+        }
+    }
+
+    #[test]
+    fn rejects_replayed_signature() {
+        let keypair = Keypair::new();
+        let signers = signers(&keypair);
+        let headers = signed_headers(&keypair, &Method::POST, PATH, BODY, now());
+
+        assert!(signers.verify(&Method::POST, PATH, BODY, &headers).is_ok());
+        let err = signers.verify(&Method::POST, PATH, BODY, &headers).unwrap_err();
+        assert!(err.contains("already used"), "{err}");
+    }
+
+    #[test]
+    fn rejects_unregistered_signer() {
+        let stranger = Keypair::new();
+        let headers = signed_headers(&stranger, &Method::POST, PATH, BODY, now());
+
+        let err = signers(&Keypair::new()).verify(&Method::POST, PATH, BODY, &headers).unwrap_err();
+        assert!(err.contains("not a registered signer"), "{err}");
+    }
+
+    #[test]
+    fn rejects_request_changed_after_signing() {
+        let keypair = Keypair::new();
+        let signers = signers(&keypair);
+        let headers = signed_headers(&keypair, &Method::POST, PATH, BODY, now());
+        let tampered = br#"{"from":"a","to":"c","lamports":1000000}"#;
+
+        for (method, path, body) in [
+            (Method::POST, PATH, &tampered[..]),
+            (Method::POST, "/send/sol?cluster=devnet", BODY),
+            (Method::PUT, PATH, BODY),
+        ] {
+            let err = signers.verify(&method, path, body, &headers).unwrap_err();
+            assert!(err.contains("does not match"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,511 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::Duration,
+};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts, HeaderMap, HeaderValue},
+    response::{AppendHeaders, IntoResponse, Json as ResponseJson},
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use crate::auth::Role;
+use crate::config::SiwsConfig;
+use crate::error::ApiError;
+use crate::extract::ApiJson;
+use crate::key_store::hash_key;
+use crate::state::AppState;
+use crate::validation::{Validate, Validator};
+use crate::ApiResponse;
+
+/// Session tokens carry this prefix so they can be told apart from API keys
+/// when sent as `Authorization: Bearer`.
+pub const SESSION_TOKEN_PREFIX: &str = "ses_";
+
+const HEADER_SUFFIX: &str = " wants you to sign in with your Solana account:";
+
+/// A Sign-In With Solana message, in the plain-text form wallets display:
+///
+/// ```text
+/// example.com wants you to sign in with your Solana account:
+/// <base58 address>
+///
+/// <optional statement>
+///
+/// URI: https://example.com
+/// Version: 1
+/// Chain ID: devnet
+/// Nonce: <nonce from /auth/nonce>
+/// Issued At: 2024-01-01T00:00:00Z
+/// Expiration Time: 2024-01-01T00:10:00Z
+/// ```
+///
+/// `Nonce`, `Issued At` and `Expiration Time` are required. `URI`, when
+/// given, must point at the same host as the domain; other fields are
+/// accepted and ignored.
+#[derive(Debug)]
+pub struct SignInMessage {
+    pub domain: String,
+    pub address: Pubkey,
+    pub uri: Option<String>,
+    pub nonce: String,
+    pub issued_at: DateTime<Utc>,
+    pub expiration_time: DateTime<Utc>,
+    pub not_before: Option<DateTime<Utc>>,
+}
+
+impl SignInMessage {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let domain = lines
+            .next()
+            .and_then(|line| line.strip_suffix(HEADER_SUFFIX))
+            .filter(|domain| !domain.is_empty())
+            .ok_or_else(|| format!("first line must be '<domain>{}'", HEADER_SUFFIX))?;
+        let address = lines
+            .next()
+            .ok_or("second line must be the account address")?
+            .trim()
+            .parse::<Pubkey>()
+            .map_err(|_| "second line is not a valid account address")?;
+
+        let mut fields = HashMap::new();
+        for line in lines {
+            if let Some((key, value)) = line.split_once(": ") {
+                fields.insert(key.trim(), value.trim());
+            }
+        }
+        let timestamp = |key: &str| -> Result<Option<DateTime<Utc>>, String> {
+            fields
+                .get(key)
+                .map(|value| {
+                    DateTime::parse_from_rfc3339(value)
+                        .map(|time| time.with_timezone(&Utc))
+                        .map_err(|_| format!("'{}' is not an RFC 3339 timestamp", key))
+                })
+                .transpose()
+        };
+
+        let uri = fields.get("URI").map(|uri| uri.to_string());
+        let nonce = fields.get("Nonce").ok_or("missing 'Nonce'")?.to_string();
+        let issued_at = timestamp("Issued At")?.ok_or("missing 'Issued At'")?;
+        let expiration_time = timestamp("Expiration Time")?.ok_or("missing 'Expiration Time'")?;
+        let not_before = timestamp("Not Before")?;
+        Ok(Self {
+            domain: domain.to_string(),
+            address,
+            uri,
+            nonce,
+            issued_at,
+            expiration_time,
+            not_before,
+        })
+    }
+}
+
+struct Session {
+    pubkey: Pubkey,
+    expires_at: DateTime<Utc>,
+}
+
+/// Outstanding nonces and live sessions. Both live in memory only, so a
+/// restart signs every wallet out.
+pub struct Siws {
+    config: SiwsConfig,
+    /// Nonce to expiry.
+    nonces: Mutex<HashMap<String, DateTime<Utc>>>,
+    /// Session token hash to session.
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn ttl(secs: u64) -> chrono::Duration {
+    chrono::Duration::from_std(Duration::from_secs(secs)).unwrap_or(chrono::Duration::MAX)
+}
+
+fn rfc3339(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+impl Siws {
+    pub fn new(config: &SiwsConfig) -> Self {
+        Self {
+            config: config.clone(),
+            nonces: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn issue_nonce(&self) -> (String, DateTime<Utc>) {
+        let now = Utc::now();
+        let nonce = hex::encode(random_bytes::<16>());
+        let expires_at = now + ttl(self.config.nonce_ttl_secs);
+        let mut nonces = self.nonces.lock().unwrap();
+        nonces.retain(|_, expiry| *expiry > now);
+        nonces.insert(nonce.clone(), expires_at);
+        (nonce, expires_at)
+    }
+
+    /// Check `message` and its `signature` and open a session for the
+    /// signer. The nonce is spent whether or not the rest checks out.
+    fn login(&self, message: &str, signature: &Signature) -> Result<(String, Pubkey, DateTime<Utc>), ApiError> {
+        let rejected = |reason: &str| ApiError::SignInRejected { reason: reason.to_string() };
+        let parsed = SignInMessage::parse(message).map_err(|reason| ApiError::InvalidSignInMessage { reason })?;
+        let now = Utc::now();
+        let skew = ttl(self.config.max_clock_skew_secs);
+
+        let nonce_expiry = self.nonces.lock().unwrap().remove(&parsed.nonce);
+        if nonce_expiry.is_none_or(|expiry| expiry <= now) {
+            return Err(rejected("nonce is unknown, expired or already used"));
+        }
+        if parsed.domain != self.config.domain {
+            return Err(rejected(&format!("message is for '{}', not '{}'", parsed.domain, self.config.domain)));
+        }
+        let uri_host = parsed.uri.as_deref().map(|uri| {
+            let authority = uri.split_once("://").map_or(uri, |(_, rest)| rest);
+            authority.split(['/', '?', '#']).next().unwrap_or_default()
+        });
+        if uri_host.is_some_and(|host| host != self.config.domain) {
+            return Err(rejected(&format!("message URI is not on '{}'", self.config.domain)));
+        }
+        if parsed.issued_at > now + skew {
+            return Err(rejected("message is issued in the future"));
+        }
+        if parsed.expiration_time <= now {
+            return Err(rejected("message has expired"));
+        }
+        if parsed.not_before.is_some_and(|not_before| not_before > now + skew) {
+            return Err(rejected("message is not valid yet"));
+        }
+        if !signature.verify(&parsed.address.to_bytes(), message.as_bytes()) {
+            return Err(rejected("signature does not match the message address"));
+        }
+
+        let token = format!("{}{}", SESSION_TOKEN_PREFIX, bs58::encode(random_bytes::<32>()).into_string());
+        let expires_at = now + ttl(self.config.session_ttl_secs);
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| session.expires_at > now);
+        sessions.insert(hash_key(&token), Session { pubkey: parsed.address, expires_at });
+        Ok((token, parsed.address, expires_at))
+    }
+
+    fn session(&self, token: &str) -> Option<(Pubkey, DateTime<Utc>)> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(&hash_key(token))?;
+        (session.expires_at > Utc::now()).then_some((session.pubkey, session.expires_at))
+    }
+
+    /// The wallet the request is signed in as, with the role sessions are
+    /// granted. `None` when no session token was sent, `Err` when one was
+    /// but it is unknown or has expired.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Option<Result<(Pubkey, Role), &'static str>> {
+        let token = self.token_from(headers)?;
+        Some(match self.session(&token) {
+            Some((pubkey, _)) => Ok((pubkey, self.config.session_role)),
+            None => Err("session is invalid or has expired"),
+        })
+    }
+
+    fn logout(&self, token: &str) {
+        self.sessions.lock().unwrap().remove(&hash_key(token));
+    }
+
+    fn cookie(&self, value: &str, max_age_secs: u64) -> HeaderValue {
+        let secure = if self.config.cookie_secure { "; Secure" } else { "" };
+        let cookie = format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{}",
+            self.config.cookie_name, value, max_age_secs, secure
+        );
+        HeaderValue::from_str(&cookie).expect("cookie name was validated")
+    }
+
+    /// Session token from `Authorization: Bearer ses_...` or the session
+    /// cookie.
+    fn token_from(&self, headers: &HeaderMap) -> Option<String> {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| token.starts_with(SESSION_TOKEN_PREFIX));
+        if let Some(token) = bearer {
+            return Some(token.to_string());
+        }
+        headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(name, _)| *name == self.config.cookie_name)
+            .map(|(_, token)| token.to_string())
+    }
+}
+
+/// The wallet a request is signed in as. Rejects the request with
+/// `UNAUTHORIZED` when there is no live session.
+pub struct WalletSession {
+    pub pubkey: Pubkey,
+    pub expires_at: DateTime<Utc>,
+    token: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for WalletSession {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let siws = &state.siws;
+        let token = siws.token_from(&parts.headers).ok_or_else(|| ApiError::Unauthorized {
+            reason: "sign in first".to_string(),
+        })?;
+        let (pubkey, expires_at) = siws.session(&token).ok_or_else(|| ApiError::Unauthorized {
+            reason: "session is invalid or has expired".to_string(),
+        })?;
+        Ok(WalletSession { pubkey, expires_at, token })
+    }
+}
+
+#[derive(Serialize)]
+pub struct NonceData {
+    nonce: String,
+    /// The message must use this domain.
+    domain: String,
+    expires_at: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LoginRequest {
+    message: Option<String>,
+    signature: Option<String>,
+}
+
+pub struct LoginParams {
+    message: String,
+    signature: Signature,
+}
+
+impl Validate for LoginRequest {
+    type Output = LoginParams;
+
+    fn validate(&self) -> Result<LoginParams, ApiError> {
+        let mut v = Validator::new();
+        let message = v.required("message", self.message.as_deref());
+        let signature = v.signature("signature", self.signature.as_deref());
+        v.finish(|| Some(LoginParams {
+            message: message?.to_string(),
+            signature: signature?,
+        }))
+    }
+}
+
+#[derive(Serialize)]
+pub struct SessionData {
+    pubkey: String,
+    /// Also set as an HttpOnly cookie; send it as `Authorization: Bearer`
+    /// from clients that do not keep cookies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    expires_at: String,
+}
+
+pub async fn nonce(State(state): State<AppState>) -> ResponseJson<ApiResponse<NonceData>> {
+    let (nonce, expires_at) = state.siws.issue_nonce();
+    ResponseJson(ApiResponse::success(NonceData {
+        nonce,
+        domain: state.siws.config.domain.clone(),
+        expires_at: rfc3339(expires_at),
+    }))
+}
+
+pub async fn login(
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<LoginRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    tracing::debug!(?payload, "request payload");
+    let LoginParams { message, signature } = payload.validate()?;
+    let (token, pubkey, expires_at) = state.siws.login(&message, &signature)?;
+    tracing::info!(%pubkey, "wallet signed in");
+
+    let cookie = state.siws.cookie(&token, state.siws.config.session_ttl_secs);
+    let data = SessionData {
+        pubkey: pubkey.to_string(),
+        token: Some(token),
+        expires_at: rfc3339(expires_at),
+    };
+    Ok((AppendHeaders([(header::SET_COOKIE, cookie)]), ResponseJson(ApiResponse::success(data))))
+}
+
+pub async fn logout(State(state): State<AppState>, session: WalletSession) -> impl IntoResponse {
+    state.siws.logout(&session.token);
+    let cookie = state.siws.cookie("", 0);
+    let data = SessionData {
+        pubkey: session.pubkey.to_string(),
+        token: None,
+        expires_at: rfc3339(Utc::now()),
+    };
+    (AppendHeaders([(header::SET_COOKIE, cookie)]), ResponseJson(ApiResponse::success(data)))
+}
+
+pub async fn session(session: WalletSession) -> ResponseJson<ApiResponse<SessionData>> {
+    ResponseJson(ApiResponse::success(SessionData {
+        pubkey: session.pubkey.to_string(),
+        token: None,
+        expires_at: rfc3339(session.expires_at),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signer::{keypair::Keypair, Signer};
+
+    use super::*;
+
+    const DOMAIN: &str = "app.example.com";
+
+    fn siws() -> Siws {
+        Siws::new(&SiwsConfig { domain: DOMAIN.to_string(), ..SiwsConfig::default() })
+    }
+
+    fn message(domain: &str, uri: &str, address: &Pubkey, nonce: &str) -> String {
+        let now = Utc::now();
+        format!(
+            "{domain}{HEADER_SUFFIX}\n{address}\n\nSign in to the app\n\nURI: {uri}\nVersion: 1\nChain ID: devnet\n\
+             Nonce: {nonce}\nIssued At: {}\nExpiration Time: {}",
+            rfc3339(now),
+            rfc3339(now + chrono::Duration::minutes(10)),
+        )
+    }
+
+    /// A message for `DOMAIN` with a fresh nonce, and its signature.
+    fn signed(siws: &Siws, keypair: &Keypair) -> (String, Signature) {
+        let (nonce, _) = siws.issue_nonce();
+        let message = message(DOMAIN, &format!("https://{DOMAIN}/login"), &keypair.pubkey(), &nonce);
+        let signature = keypair.sign_message(message.as_bytes());
+        (message, signature)
+    }
+
+    fn rejection(result: Result<(String, Pubkey, DateTime<Utc>), ApiError>) -> String {
+        match result {
+            Err(ApiError::SignInRejected { reason }) => reason,
+            other => panic!("expected a rejection, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_wallet_message() {
+        let address = Pubkey::new_unique();
+        let parsed = SignInMessage::parse(&message(DOMAIN, "https://app.example.com", &address, "abc123")).unwrap();
+        assert_eq!(parsed.domain, DOMAIN);
+        assert_eq!(parsed.address, address);
+        assert_eq!(parsed.uri.as_deref(), Some("https://app.example.com"));
+        assert_eq!(parsed.nonce, "abc123");
+        assert!(parsed.expiration_time > parsed.issued_at);
+        assert_eq!(parsed.not_before, None);
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let address = Pubkey::new_unique();
+        let valid = message(DOMAIN, "https://app.example.com", &address, "abc123");
+        let cases = [
+            valid.replacen(HEADER_SUFFIX, " wants you to log in:", 1),
+            valid.replacen(&address.to_string(), "not-an-address", 1),
+            valid.replacen("Nonce: abc123\n", "", 1),
+            valid.replacen("Issued At: ", "Issued At: yesterday ", 1),
+            valid.lines().filter(|line| !line.starts_with("Expiration Time")).collect::<Vec<_>>().join("\n"),
+            String::new(),
+        ];
+        for case in cases {
+            assert!(SignInMessage::parse(&case).is_err(), "accepted {case:?}");
+        }
+    }
+
+    #[test]
+    fn login_opens_a_session() {
+        let siws = siws();
+        let keypair = Keypair::new();
+        let (message, signature) = signed(&siws, &keypair);
+
+        let (token, pubkey, _) = siws.login(&message, &signature).unwrap();
+        assert!(token.starts_with(SESSION_TOKEN_PREFIX));
+        assert_eq!(pubkey, keypair.pubkey());
+        assert_eq!(siws.session(&token).unwrap().0, keypair.pubkey());
+    }
+
+    #[test]
+    fn rejects_other_domain_or_uri() {
+        let siws = siws();
+        let keypair = Keypair::new();
+        for (domain, uri) in [
+            ("evil.example.com", "https://app.example.com"),
+            (DOMAIN, "https://evil.example.com/login"),
+        ] {
+            let (nonce, _) = siws.issue_nonce();
+            let message = message(domain, uri, &keypair.pubkey(), &nonce);
+            let signature = keypair.sign_message(message.as_bytes());
+            rejection(siws.login(&message, &signature));
+        }
+    }
+
+    #[test]
+    fn nonce_is_single_use() {
+        let siws = siws();
+        let keypair = Keypair::new();
+        let (message, signature) = signed(&siws, &keypair);
+
+        assert!(siws.login(&message, &signature).is_ok());
+        assert!(rejection(siws.login(&message, &signature)).contains("nonce"));
+    }
+
+    #[test]
+    fn expired_nonce_is_rejected() {
+        let siws = siws();
+        let keypair = Keypair::new();
+        let (message, signature) = signed(&siws, &keypair);
+        for expiry in siws.nonces.lock().unwrap().values_mut() {
+            *expiry = Utc::now() - chrono::Duration::seconds(1);
+        }
+
+        assert!(rejection(siws.login(&message, &signature)).contains("nonce"));
+    }
+
+    #[test]
+    fn bad_signature_is_rejected() {
+        let siws = siws();
+        let keypair = Keypair::new();
+        let (message, _) = signed(&siws, &keypair);
+        let signature = Keypair::new().sign_message(message.as_bytes());
+
+        assert!(rejection(siws.login(&message, &signature)).contains("signature"));
+    }
+
+    #[test]
+    fn session_expires_and_logs_out() {
+        let siws = siws();
+        let keypair = Keypair::new();
+        let (message, signature) = signed(&siws, &keypair);
+        let (token, _, _) = siws.login(&message, &signature).unwrap();
+
+        siws.logout(&token);
+        assert!(siws.session(&token).is_none());
+
+        let (message, signature) = signed(&siws, &keypair);
+        let (token, _, _) = siws.login(&message, &signature).unwrap();
+        for session in siws.sessions.lock().unwrap().values_mut() {
+            session.expires_at = Utc::now() - chrono::Duration::seconds(1);
+        }
+        assert!(siws.session(&token).is_none());
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use tokio_util::sync::CancellationToken;
+
+use crate::cluster::Clusters;
+use crate::config::Config;
+use crate::key_store::{KeyStore, KeyStoreError};
+use crate::metrics::Metrics;
+use crate::rate_limit::RateLimiter;
+use crate::signed_request::RequestSigners;
+use crate::siws::Siws;
+
+/// Shared, cheaply clonable state handed to every handler through axum's
+/// `State` extractor.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    /// One long-lived client per configured cluster so HTTP connections to
+    /// the nodes are reused. Each carries the configured commitment, which
+    /// every RPC call defaults to.
+    pub clusters: Arc<Clusters>,
+    pub metrics: Arc<Metrics>,
+    pub rate_limiter: Arc<RateLimiter>,
+    /// API keys; `None` when authentication is disabled.
+    pub key_store: Option<Arc<KeyStore>>,
+    /// Keypairs allowed to sign requests, when auth is enabled.
+    pub request_signers: Option<Arc<RequestSigners>>,
+    /// Sign-In With Solana nonces and sessions.
+    pub siws: Arc<Siws>,
+    /// Cancelled when the process starts shutting down. Background tasks
+    /// stop on it and `/readyz` reports not ready once it fires.
+    pub shutdown: CancellationToken,
+}
+
+impl AppState {
+    pub fn new(config: Config) -> Result<Self, KeyStoreError> {
+        let metrics = Arc::new(Metrics::new());
+        let clusters = Clusters::new(&config.rpc, metrics.clone());
+        let rate_limiter = RateLimiter::new(&config.rate_limit);
+        let request_signers = config.auth.enabled.then(|| Arc::new(RequestSigners::new(&config.auth)));
+        let siws = Siws::new(&config.siws);
+        let key_store = match config.auth.enabled {
+            true => Some(Arc::new(KeyStore::open(&config.auth.keys_file)?)),
+            false => None,
+        };
+        Ok(Self {
+            config: Arc::new(config),
+            clusters: Arc::new(clusters),
+            metrics,
+            rate_limiter: Arc::new(rate_limiter),
+            key_store,
+            request_signers,
+            siws: Arc::new(siws),
+            shutdown: CancellationToken::new(),
+        })
+    }
+}
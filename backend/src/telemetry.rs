@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{HeaderName, Request, Response},
+    Router,
+};
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use tracing::Span;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::{LogFormat, LoggingConfig};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Install the global tracing subscriber. `RUST_LOG`, when set, takes
+/// precedence over the configured filter.
+pub fn init(config: &LoggingConfig) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.filter));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    match config.format {
+        LogFormat::Pretty => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+}
+
+/// Give every request an `X-Request-Id` (reusing the caller's if present),
+/// echo it on the response and wrap the request in a span carrying method,
+/// path and request id, with status and latency recorded once the response
+/// is ready.
+pub fn with_request_tracing<S>(router: Router<S>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let request_id_header = HeaderName::from_static(REQUEST_ID_HEADER);
+
+    let trace = TraceLayer::new_for_http()
+        .make_span_with(|request: &Request<Body>| {
+            let request_id = request
+                .headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            tracing::info_span!(
+                "request",
+                method = %request.method(),
+                path = %request.uri().path(),
+                request_id = %request_id,
+                api_key = tracing::field::Empty,
+                status = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+            )
+        })
+        .on_request(())
+        .on_response(|response: &Response<Body>, latency: Duration, span: &Span| {
+            span.record("status", response.status().as_u16());
+            span.record("latency_ms", latency.as_millis() as u64);
+            tracing::info!("request completed");
+        });
+
+    // Layers run outermost-last: the id must be set before the span is made.
+    router
+        .layer(PropagateRequestIdLayer::new(request_id_header.clone()))
+        .layer(trace)
+        .layer(SetRequestIdLayer::new(request_id_header, MakeRequestUuid))
+}
@@ -0,0 +1,386 @@
+use serde::Deserialize;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_token_2022::{
+    extension::{
+        default_account_state::instruction::initialize_default_account_state,
+        interest_bearing_mint, metadata_pointer,
+        transfer_fee::{instruction::initialize_transfer_fee_config, MAX_FEE_BASIS_POINTS},
+        ExtensionType,
+    },
+    instruction::{initialize_mint_close_authority, initialize_non_transferable_mint, initialize_permanent_delegate},
+    state::{AccountState, Mint},
+};
+
+use crate::error::ApiError;
+use crate::validation::Validator;
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TransferFeeRequest {
+    #[serde(rename = "basisPoints")]
+    basis_points: u16,
+    #[serde(rename = "maxFee")]
+    max_fee: u64,
+    /// Defaults to the mint authority.
+    #[serde(rename = "configAuthority")]
+    config_authority: Option<String>,
+    /// Defaults to the mint authority.
+    #[serde(rename = "withdrawAuthority")]
+    withdraw_authority: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct InterestBearingRequest {
+    /// Annual rate in basis points; may be negative.
+    rate: i16,
+    /// Defaults to the mint authority.
+    #[serde(rename = "rateAuthority")]
+    rate_authority: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MetadataPointerRequest {
+    /// Defaults to the mint authority.
+    authority: Option<String>,
+    /// Defaults to the mint itself, for metadata stored in the mint account.
+    #[serde(rename = "metadataAddress")]
+    metadata_address: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultAccountState {
+    Initialized,
+    Frozen,
+}
+
+/// The optional `extensions` object on `/token/create`. Only valid with the
+/// Token-2022 program. Unknown keys are rejected rather than ignored, so a
+/// misspelt extension cannot silently produce a plain mint.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MintExtensionsRequest {
+    #[serde(rename = "transferFee")]
+    transfer_fee: Option<TransferFeeRequest>,
+    #[serde(rename = "interestBearing")]
+    interest_bearing: Option<InterestBearingRequest>,
+    #[serde(rename = "nonTransferable", default)]
+    non_transferable: bool,
+    #[serde(rename = "permanentDelegate")]
+    permanent_delegate: Option<String>,
+    #[serde(rename = "defaultAccountState")]
+    default_account_state: Option<DefaultAccountState>,
+    #[serde(rename = "mintCloseAuthority")]
+    mint_close_authority: Option<String>,
+    #[serde(rename = "metadataPointer")]
+    metadata_pointer: Option<MetadataPointerRequest>,
+}
+
+struct TransferFee {
+    basis_points: u16,
+    max_fee: u64,
+    config_authority: Option<Pubkey>,
+    withdraw_authority: Option<Pubkey>,
+}
+
+struct InterestBearing {
+    rate: i16,
+    rate_authority: Option<Pubkey>,
+}
+
+struct MetadataPointer {
+    authority: Option<Pubkey>,
+    metadata_address: Option<Pubkey>,
+}
+
+/// Validated mint extensions. Authorities left as `None` fall back to the
+/// mint authority when the instructions are built.
+pub struct MintExtensions {
+    transfer_fee: Option<TransferFee>,
+    interest_bearing: Option<InterestBearing>,
+    non_transferable: bool,
+    permanent_delegate: Option<Pubkey>,
+    default_account_state: Option<DefaultAccountState>,
+    mint_close_authority: Option<Pubkey>,
+    metadata_pointer: Option<MetadataPointer>,
+}
+
+impl TransferFeeRequest {
+    fn validate_with(&self, v: &mut Validator) -> Option<TransferFee> {
+        v.check(self.basis_points <= MAX_FEE_BASIS_POINTS, || ApiError::InvalidExtension {
+            field: "extensions.transferFee.basisPoints",
+            reason: format!("must be at most {MAX_FEE_BASIS_POINTS}"),
+        });
+        let config_authority = v.optional_pubkey("extensions.transferFee.configAuthority", self.config_authority.as_deref());
+        let withdraw_authority = v.optional_pubkey("extensions.transferFee.withdrawAuthority", self.withdraw_authority.as_deref());
+        Some(TransferFee {
+            basis_points: self.basis_points,
+            max_fee: self.max_fee,
+            config_authority: config_authority?,
+            withdraw_authority: withdraw_authority?,
+        })
+    }
+}
+
+impl InterestBearingRequest {
+    fn validate_with(&self, v: &mut Validator) -> Option<InterestBearing> {
+        let rate_authority = v.optional_pubkey("extensions.interestBearing.rateAuthority", self.rate_authority.as_deref());
+        Some(InterestBearing {
+            rate: self.rate,
+            rate_authority: rate_authority?,
+        })
+    }
+}
+
+impl MetadataPointerRequest {
+    fn validate_with(&self, v: &mut Validator) -> Option<MetadataPointer> {
+        let authority = v.optional_pubkey("extensions.metadataPointer.authority", self.authority.as_deref());
+        let metadata_address = v.optional_pubkey("extensions.metadataPointer.metadataAddress", self.metadata_address.as_deref());
+        Some(MetadataPointer {
+            authority: authority?,
+            metadata_address: metadata_address?,
+        })
+    }
+}
+
+impl MintExtensionsRequest {
+    pub fn is_empty(&self) -> bool {
+        self.transfer_fee.is_none()
+            && self.interest_bearing.is_none()
+            && !self.non_transferable
+            && self.permanent_delegate.is_none()
+            && self.default_account_state.is_none()
+            && self.mint_close_authority.is_none()
+            && self.metadata_pointer.is_none()
+    }
+
+    pub fn frozen_by_default(&self) -> bool {
+        self.default_account_state == Some(DefaultAccountState::Frozen)
+    }
+
+    /// Record any field errors on the caller's validator, so they are
+    /// reported together with the rest of the request.
+    pub fn validate_with(&self, v: &mut Validator) -> Option<MintExtensions> {
+        let transfer_fee = match &self.transfer_fee {
+            None => Some(None),
+            Some(fee) => fee.validate_with(v).map(Some),
+        };
+        let interest_bearing = match &self.interest_bearing {
+            None => Some(None),
+            Some(interest) => interest.validate_with(v).map(Some),
+        };
+        let permanent_delegate = v.optional_pubkey("extensions.permanentDelegate", self.permanent_delegate.as_deref());
+        let mint_close_authority = v.optional_pubkey("extensions.mintCloseAuthority", self.mint_close_authority.as_deref());
+        let metadata_pointer = match &self.metadata_pointer {
+            None => Some(None),
+            Some(pointer) => pointer.validate_with(v).map(Some),
+        };
+
+        Some(MintExtensions {
+            transfer_fee: transfer_fee?,
+            interest_bearing: interest_bearing?,
+            non_transferable: self.non_transferable,
+            permanent_delegate: permanent_delegate?,
+            default_account_state: self.default_account_state,
+            mint_close_authority: mint_close_authority?,
+            metadata_pointer: metadata_pointer?,
+        })
+    }
+}
+
+impl MintExtensions {
+    fn types(&self) -> Vec<ExtensionType> {
+        let mut types = Vec::new();
+        if self.transfer_fee.is_some() {
+            types.push(ExtensionType::TransferFeeConfig);
+        }
+        if self.interest_bearing.is_some() {
+            types.push(ExtensionType::InterestBearingConfig);
+        }
+        if self.non_transferable {
+            types.push(ExtensionType::NonTransferable);
+        }
+        if self.permanent_delegate.is_some() {
+            types.push(ExtensionType::PermanentDelegate);
+        }
+        if self.default_account_state.is_some() {
+            types.push(ExtensionType::DefaultAccountState);
+        }
+        if self.mint_close_authority.is_some() {
+            types.push(ExtensionType::MintCloseAuthority);
+        }
+        if self.metadata_pointer.is_some() {
+            types.push(ExtensionType::MetadataPointer);
+        }
+        types
+    }
+
+    /// Size of the mint account with room for every extension. Equal to the
+    /// plain `Mint::LEN` when there are none.
+    pub fn account_len(&self) -> Result<usize, ApiError> {
+        ExtensionType::try_calculate_account_len::<Mint>(&self.types())
+            .map_err(|e| ApiError::InstructionFailed(e.to_string()))
+    }
+
+    /// Extension init instructions, which must run after the mint account is
+    /// allocated and before `initialize_mint2`.
+    pub fn instructions(
+        &self,
+        token_program: &Pubkey,
+        mint: &Pubkey,
+        mint_authority: &Pubkey,
+    ) -> Result<Vec<Instruction>, ApiError> {
+        let or_mint_authority = |authority: Option<Pubkey>| authority.unwrap_or(*mint_authority);
+        let mut instructions = Vec::new();
+
+        if let Some(fee) = &self.transfer_fee {
+            instructions.push(initialize_transfer_fee_config(
+                token_program,
+                mint,
+                Some(&or_mint_authority(fee.config_authority)),
+                Some(&or_mint_authority(fee.withdraw_authority)),
+                fee.basis_points,
+                fee.max_fee,
+            ));
+        }
+        if let Some(interest) = &self.interest_bearing {
+            instructions.push(interest_bearing_mint::instruction::initialize(
+                token_program,
+                mint,
+                Some(or_mint_authority(interest.rate_authority)),
+                interest.rate,
+            ));
+        }
+        if self.non_transferable {
+            instructions.push(initialize_non_transferable_mint(token_program, mint));
+        }
+        if let Some(delegate) = &self.permanent_delegate {
+            instructions.push(initialize_permanent_delegate(token_program, mint, delegate));
+        }
+        if let Some(state) = self.default_account_state {
+            let state = match state {
+                DefaultAccountState::Initialized => AccountState::Initialized,
+                DefaultAccountState::Frozen => AccountState::Frozen,
+            };
+            instructions.push(initialize_default_account_state(token_program, mint, &state));
+        }
+        if let Some(close_authority) = &self.mint_close_authority {
+            instructions.push(initialize_mint_close_authority(token_program, mint, Some(close_authority)));
+        }
+        if let Some(pointer) = &self.metadata_pointer {
+            instructions.push(metadata_pointer::instruction::initialize(
+                token_program,
+                mint,
+                Some(or_mint_authority(pointer.authority)),
+                Some(pointer.metadata_address.unwrap_or(*mint)),
+            ));
+        }
+
+        instructions
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .map_err(|e| ApiError::InstructionFailed(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use solana_sdk::program_pack::Pack;
+    use spl_token_2022::instruction::TokenInstruction;
+
+    use super::*;
+
+    fn extensions(body: serde_json::Value) -> MintExtensions {
+        let request: MintExtensionsRequest = serde_json::from_value(body).unwrap();
+        let mut v = Validator::new();
+        let extensions = request.validate_with(&mut v);
+        v.finish(|| extensions).unwrap()
+    }
+
+    fn all_extensions() -> MintExtensions {
+        extensions(json!({
+            "transferFee": { "basisPoints": 50, "maxFee": 5_000 },
+            "interestBearing": { "rate": 300 },
+            "nonTransferable": true,
+            "permanentDelegate": Pubkey::new_unique().to_string(),
+            "defaultAccountState": "frozen",
+            "mintCloseAuthority": Pubkey::new_unique().to_string(),
+            "metadataPointer": {},
+        }))
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let misspelt = json!({ "transferFees": { "basisPoints": 50, "maxFee": 5_000 } });
+        assert!(serde_json::from_value::<MintExtensionsRequest>(misspelt).is_err());
+        let nested = json!({ "transferFee": { "basisPoints": 50, "maxFee": 5_000, "maximumFee": 1 } });
+        assert!(serde_json::from_value::<MintExtensionsRequest>(nested).is_err());
+    }
+
+    #[test]
+    fn account_len_covers_each_extension() {
+        assert_eq!(extensions(json!({})).account_len().unwrap(), Mint::LEN);
+        // 165 byte base account, 1 byte account type, then a 4 byte TLV
+        // header plus the extension's data for each extension.
+        assert_eq!(extensions(json!({ "nonTransferable": true })).account_len().unwrap(), 166 + 4);
+        assert_eq!(
+            extensions(json!({
+                "transferFee": { "basisPoints": 50, "maxFee": 5_000 },
+                "mintCloseAuthority": Pubkey::new_unique().to_string(),
+            }))
+            .account_len()
+            .unwrap(),
+            166 + (4 + 108) + (4 + 32)
+        );
+        assert!(all_extensions().account_len().unwrap() > Mint::LEN);
+    }
+
+    #[test]
+    fn instructions_follow_declared_order() {
+        let mint = Pubkey::new_unique();
+        let mint_authority = Pubkey::new_unique();
+        let instructions = all_extensions()
+            .instructions(&spl_token_2022::ID, &mint, &mint_authority)
+            .unwrap();
+
+        let kinds: Vec<&str> = instructions
+            .iter()
+            .map(|ix| {
+                assert_eq!(ix.program_id, spl_token_2022::ID);
+                assert_eq!(ix.accounts[0].pubkey, mint);
+                match TokenInstruction::unpack(&ix.data).unwrap() {
+                    TokenInstruction::TransferFeeExtension(_) => "transferFee",
+                    TokenInstruction::InterestBearingMintExtension => "interestBearing",
+                    TokenInstruction::InitializeNonTransferableMint => "nonTransferable",
+                    TokenInstruction::InitializePermanentDelegate { .. } => "permanentDelegate",
+                    TokenInstruction::DefaultAccountStateExtension => "defaultAccountState",
+                    TokenInstruction::InitializeMintCloseAuthority { .. } => "mintCloseAuthority",
+                    TokenInstruction::MetadataPointerExtension => "metadataPointer",
+                    other => panic!("unexpected instruction {other:?}"),
+                }
+            })
+            .collect();
+        assert_eq!(kinds, [
+            "transferFee",
+            "interestBearing",
+            "nonTransferable",
+            "permanentDelegate",
+            "defaultAccountState",
+            "mintCloseAuthority",
+            "metadataPointer",
+        ]);
+    }
+
+    #[test]
+    fn basis_points_are_capped() {
+        let request: MintExtensionsRequest =
+            serde_json::from_value(json!({ "transferFee": { "basisPoints": 10_001, "maxFee": 0 } })).unwrap();
+        let mut v = Validator::new();
+        let extensions = request.validate_with(&mut v);
+        let err = v.finish(|| extensions).err().unwrap();
+        assert_eq!(err.body().errors[0].field.as_deref(), Some("extensions.transferFee.basisPoints"));
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::cluster::LazyCluster;
+use crate::error::ApiError;
+
+/// Which SPL token program owns a mint and its token accounts.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenProgram {
+    #[default]
+    #[serde(rename = "spl-token")]
+    Token,
+    #[serde(rename = "token-2022")]
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn id(self) -> Pubkey {
+        match self {
+            TokenProgram::Token => spl_token::ID,
+            TokenProgram::Token2022 => spl_token_2022::ID,
+        }
+    }
+
+    pub fn from_owner(owner: &Pubkey) -> Option<Self> {
+        [TokenProgram::Token, TokenProgram::Token2022]
+            .into_iter()
+            .find(|program| program.id() == *owner)
+    }
+}
+
+/// Use the program the client asked for, or look up the owner of `mint` on
+/// `cluster` when it did not say.
+pub async fn resolve(
+    cluster: &LazyCluster,
+    requested: Option<TokenProgram>,
+    mint: Pubkey,
+) -> Result<TokenProgram, ApiError> {
+    if let Some(program) = requested {
+        return Ok(program);
+    }
+
+    let account = cluster
+        .get()?
+        .rpc
+        .read("getAccountInfo", |rpc| async move {
+            rpc.get_account_with_commitment(&mint, rpc.commitment()).await
+        })
+        .await?
+        .value
+        .ok_or_else(|| ApiError::InvalidAccount {
+            field: "mint",
+            reason: "Mint account not found; pass tokenProgram explicitly".to_string(),
+        })?;
+
+    TokenProgram::from_owner(&account.owner).ok_or_else(|| ApiError::InvalidAccount {
+        field: "mint",
+        reason: format!("Mint account is owned by {}, not a token program", account.owner),
+    })
+}
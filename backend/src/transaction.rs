@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_sdk::{
+    instruction::Instruction,
+    message::{v0, Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+
+use crate::cluster::Cluster;
+use crate::error::ApiError;
+use crate::validation::{Validate, Validator};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionVersion {
+    #[default]
+    Legacy,
+    V0,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionEncoding {
+    #[default]
+    Base64,
+    Base58,
+}
+
+/// The optional `transaction` object on builder requests. When present, the
+/// instructions are also returned as a whole unsigned transaction.
+#[derive(Deserialize, Debug)]
+pub struct TransactionRequest {
+    #[serde(rename = "feePayer")]
+    fee_payer: Option<String>,
+    #[serde(default)]
+    version: TransactionVersion,
+    #[serde(default)]
+    encoding: TransactionEncoding,
+}
+
+pub struct TransactionParams {
+    fee_payer: Pubkey,
+    version: TransactionVersion,
+    encoding: TransactionEncoding,
+}
+
+impl Validate for TransactionRequest {
+    type Output = TransactionParams;
+
+    fn validate(&self) -> Result<TransactionParams, ApiError> {
+        let mut v = Validator::new();
+        let fee_payer = v.pubkey("transaction.feePayer", self.fee_payer.as_deref());
+        v.finish(|| Some(TransactionParams {
+            fee_payer: fee_payer?,
+            version: self.version,
+            encoding: self.encoding,
+        }))
+    }
+}
+
+#[derive(Serialize)]
+pub struct TransactionData {
+    /// Serialized transaction with every signature zeroed, ready for a
+    /// wallet to sign.
+    transaction: String,
+    encoding: TransactionEncoding,
+    version: TransactionVersion,
+    /// Base58 hash of the message the signers sign.
+    message_hash: String,
+    recent_blockhash: String,
+    /// The transaction is rejected once the chain passes this height.
+    last_valid_block_height: u64,
+    fee_payer: String,
+    /// Accounts that must sign, fee payer first, in signature order.
+    signers: Vec<String>,
+}
+
+/// Compile `instructions` into an unsigned transaction against a recent
+/// blockhash from `cluster`.
+pub async fn build(
+    cluster: &Cluster,
+    instructions: &[Instruction],
+    params: &TransactionParams,
+) -> Result<TransactionData, ApiError> {
+    let (blockhash, last_valid_block_height) = cluster
+        .rpc
+        .read("getLatestBlockhash", |rpc| async move {
+            rpc.get_latest_blockhash_with_commitment(rpc.commitment()).await
+        })
+        .await?;
+
+    let message = match params.version {
+        TransactionVersion::Legacy => VersionedMessage::Legacy(Message::new_with_blockhash(
+            instructions,
+            Some(&params.fee_payer),
+            &blockhash,
+        )),
+        TransactionVersion::V0 => VersionedMessage::V0(
+            v0::Message::try_compile(&params.fee_payer, instructions, &[], blockhash)
+                .map_err(|e| ApiError::InstructionFailed(e.to_string()))?,
+        ),
+    };
+    let signer_count = usize::from(message.header().num_required_signatures);
+    let signers = message.static_account_keys()[..signer_count]
+        .iter()
+        .map(Pubkey::to_string)
+        .collect();
+    let message_hash = message.hash().to_string();
+
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default(); signer_count],
+        message,
+    };
+    let bytes = bincode::serialize(&transaction).map_err(|e| ApiError::InstructionFailed(e.to_string()))?;
+    let transaction = match params.encoding {
+        TransactionEncoding::Base64 => BASE64.encode(bytes),
+        TransactionEncoding::Base58 => bs58::encode(bytes).into_string(),
+    };
+
+    Ok(TransactionData {
+        transaction,
+        encoding: params.encoding,
+        version: params.version,
+        message_hash,
+        recent_blockhash: blockhash.to_string(),
+        last_valid_block_height,
+        fee_payer: params.fee_payer.to_string(),
+        signers,
+    })
+}
@@ -0,0 +1,169 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use crate::error::ApiError;
+use crate::parse_pubkey_flexible;
+
+/// Implemented by request payloads that need checking before a handler can
+/// use them. `Output` is the parsed, known-good form of the request.
+pub trait Validate {
+    type Output;
+
+    fn validate(&self) -> Result<Self::Output, ApiError>;
+}
+
+/// Collects every field error in a request instead of stopping at the first.
+///
+/// Each check returns `Some(value)` on success and records an error
+/// otherwise, so dependent checks can be skipped with `if let`. Call
+/// [`Validator::finish`] once all fields have been visited.
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<ApiError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, error: ApiError) {
+        self.errors.push(error);
+    }
+
+    /// Record `error` unless `ok` holds.
+    pub fn check(&mut self, ok: bool, error: impl FnOnce() -> ApiError) {
+        if !ok {
+            self.push(error());
+        }
+    }
+
+    /// `None` and `""` both count as missing.
+    pub fn required<'a>(&mut self, field: &'static str, value: Option<&'a str>) -> Option<&'a str> {
+        match value {
+            None | Some("") => {
+                self.push(ApiError::MissingField { field });
+                None
+            }
+            Some(v) => Some(v),
+        }
+    }
+
+    pub fn pubkey(&mut self, field: &'static str, value: Option<&str>) -> Option<Pubkey> {
+        let value = self.required(field, value)?;
+        match parse_pubkey_flexible(value) {
+            Ok(pubkey) => Some(pubkey),
+            Err(reason) => {
+                self.push(ApiError::InvalidPubkey { field, reason });
+                None
+            }
+        }
+    }
+
+    /// `Some(None)` when the field is omitted, `None` when it is present but
+    /// invalid.
+    pub fn optional_pubkey(&mut self, field: &'static str, value: Option<&str>) -> Option<Option<Pubkey>> {
+        match value {
+            None => Some(None),
+            Some(value) => self.pubkey(field, Some(value)).map(Some),
+        }
+    }
+
+    /// A base64 encoded ed25519 signature.
+    pub fn signature(&mut self, field: &'static str, value: Option<&str>) -> Option<Signature> {
+        let value = self.required(field, value)?;
+        let signature = BASE64
+            .decode(value)
+            .map_err(|_| "not valid base64")
+            .and_then(|bytes| Signature::try_from(bytes.as_slice()).map_err(|_| "must be 64 bytes"));
+        match signature {
+            Ok(signature) => Some(signature),
+            Err(reason) => {
+                self.push(ApiError::InvalidSignature { field, reason: reason.to_string() });
+                None
+            }
+        }
+    }
+
+    /// A token or lamport amount: present, non-zero and not absurdly large.
+    pub fn amount(&mut self, field: &'static str, value: Option<u64>) -> Option<u64> {
+        let reason = match value {
+            None => {
+                self.push(ApiError::MissingField { field });
+                return None;
+            }
+            Some(0) => "must be greater than 0",
+            Some(amount) if amount > u64::MAX / 2 => "amount too large",
+            Some(amount) => return Some(amount),
+        };
+        self.push(ApiError::InvalidAmount { field, reason: reason.to_string() });
+        None
+    }
+
+    /// SPL tokens use 0-9 decimals.
+    pub fn decimals(&mut self, field: &'static str, value: u8) -> Option<u8> {
+        if value > 9 {
+            self.push(ApiError::InvalidDecimals { field });
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Return the built output if no errors were recorded. Otherwise every
+    /// error, even a lone one, is wrapped in [`ApiError::Validation`] so
+    /// clients always find field errors in the `errors` array.
+    ///
+    /// `build` is only called when every check passed, so it can use `?` on
+    /// the `Option`s returned by the checks.
+    pub fn finish<T>(self, build: impl FnOnce() -> Option<T>) -> Result<T, ApiError> {
+        if self.errors.is_empty() {
+            Ok(build().expect("validator recorded no error for a missing value"))
+        } else {
+            Err(ApiError::Validation(self.errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_error_is_wrapped() {
+        let mut v = Validator::new();
+        let pubkey = v.pubkey("owner", None);
+        let err = v.finish(|| pubkey).unwrap_err();
+
+        let body = err.body();
+        assert_eq!(body.code, "VALIDATION_FAILED");
+        assert_eq!(body.field, None);
+        assert_eq!(body.errors.len(), 1);
+        assert_eq!(body.errors[0].code, "MISSING_FIELD");
+        assert_eq!(body.errors[0].field.as_deref(), Some("owner"));
+    }
+
+    #[test]
+    fn every_error_is_reported() {
+        let mut v = Validator::new();
+        let pubkey = v.pubkey("owner", Some("not-a-key"));
+        let amount = v.amount("amount", Some(0));
+        let decimals = v.decimals("decimals", 12);
+        let err = v.finish(|| Some((pubkey?, amount?, decimals?))).unwrap_err();
+
+        let body = err.body();
+        assert_eq!(body.code, "VALIDATION_FAILED");
+        let errors: Vec<_> = body.errors.iter().map(|e| (e.code, e.field.as_deref())).collect();
+        assert_eq!(errors, [
+            ("INVALID_PUBKEY", Some("owner")),
+            ("INVALID_AMOUNT", Some("amount")),
+            ("INVALID_DECIMALS", Some("decimals")),
+        ]);
+    }
+
+    #[test]
+    fn no_errors_builds_output() {
+        let mut v = Validator::new();
+        let amount = v.amount("amount", Some(5));
+        assert_eq!(v.finish(|| amount).unwrap(), 5);
+    }
+}